@@ -0,0 +1,451 @@
+use crate::timesheet::DayOff;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// The regions that have a holiday calendar, as ISO 3166 codes
+pub const REGIONS: [&str; 8] = [
+    "GB-ENG", "GB-WLS", "GB-SCT", "GB-NIR", "IE", "US", "DE", "FR",
+];
+
+// When a holiday falls in a year
+#[derive(Clone, Copy)]
+enum Rule {
+    // a day and month
+    Fixed(u32, u32),
+    // days after Easter Sunday, negative for before
+    Easter(i64),
+    // the nth weekday of a month e.g. the first Monday in May
+    Nth(u32, Weekday, u32),
+    Last(u32, Weekday),
+    // a day and month when it is a Friday, otherwise the Monday after it
+    FridayOrMonday(u32, u32),
+}
+
+// What happens when a holiday with a fixed date falls at the weekend
+#[derive(Clone, Copy, PartialEq)]
+enum Weekend {
+    // nothing, the holiday is lost
+    Keep,
+    // the next weekday that isn't already a holiday is taken instead, as in the UK
+    Substitute,
+    // Saturdays are observed on the Friday before and Sundays on the Monday after, as in the US
+    Observe,
+}
+
+struct Holiday {
+    name: &'static str,
+    rule: Rule,
+    // the first year the holiday was given
+    since: i32,
+}
+
+const fn holiday(name: &'static str, rule: Rule) -> Holiday {
+    Holiday {
+        name,
+        rule,
+        since: 0,
+    }
+}
+
+const ENGLAND_AND_WALES: [Holiday; 8] = [
+    holiday("New Year's Day", Rule::Fixed(1, 1)),
+    holiday("Good Friday", Rule::Easter(-2)),
+    holiday("Easter Monday", Rule::Easter(1)),
+    holiday("Early May bank holiday", Rule::Nth(5, Weekday::Mon, 1)),
+    holiday("Spring bank holiday", Rule::Last(5, Weekday::Mon)),
+    holiday("Summer bank holiday", Rule::Last(8, Weekday::Mon)),
+    holiday("Christmas Day", Rule::Fixed(25, 12)),
+    holiday("Boxing Day", Rule::Fixed(26, 12)),
+];
+
+const SCOTLAND: [Holiday; 9] = [
+    holiday("New Year's Day", Rule::Fixed(1, 1)),
+    holiday("2nd January", Rule::Fixed(2, 1)),
+    holiday("Good Friday", Rule::Easter(-2)),
+    holiday("Early May bank holiday", Rule::Nth(5, Weekday::Mon, 1)),
+    holiday("Spring bank holiday", Rule::Last(5, Weekday::Mon)),
+    holiday("Summer bank holiday", Rule::Nth(8, Weekday::Mon, 1)),
+    holiday("St Andrew's Day", Rule::Fixed(30, 11)),
+    holiday("Christmas Day", Rule::Fixed(25, 12)),
+    holiday("Boxing Day", Rule::Fixed(26, 12)),
+];
+
+const NORTHERN_IRELAND: [Holiday; 10] = [
+    holiday("New Year's Day", Rule::Fixed(1, 1)),
+    holiday("St Patrick's Day", Rule::Fixed(17, 3)),
+    holiday("Good Friday", Rule::Easter(-2)),
+    holiday("Easter Monday", Rule::Easter(1)),
+    holiday("Early May bank holiday", Rule::Nth(5, Weekday::Mon, 1)),
+    holiday("Spring bank holiday", Rule::Last(5, Weekday::Mon)),
+    holiday("Battle of the Boyne", Rule::Fixed(12, 7)),
+    holiday("Summer bank holiday", Rule::Last(8, Weekday::Mon)),
+    holiday("Christmas Day", Rule::Fixed(25, 12)),
+    holiday("Boxing Day", Rule::Fixed(26, 12)),
+];
+
+const IRELAND: [Holiday; 10] = [
+    holiday("New Year's Day", Rule::Fixed(1, 1)),
+    Holiday {
+        name: "St Brigid's Day",
+        rule: Rule::FridayOrMonday(1, 2),
+        since: 2023,
+    },
+    holiday("St Patrick's Day", Rule::Fixed(17, 3)),
+    holiday("Easter Monday", Rule::Easter(1)),
+    holiday("May bank holiday", Rule::Nth(5, Weekday::Mon, 1)),
+    holiday("June bank holiday", Rule::Nth(6, Weekday::Mon, 1)),
+    holiday("August bank holiday", Rule::Nth(8, Weekday::Mon, 1)),
+    holiday("October bank holiday", Rule::Last(10, Weekday::Mon)),
+    holiday("Christmas Day", Rule::Fixed(25, 12)),
+    holiday("St Stephen's Day", Rule::Fixed(26, 12)),
+];
+
+const UNITED_STATES: [Holiday; 11] = [
+    holiday("New Year's Day", Rule::Fixed(1, 1)),
+    holiday("Martin Luther King Jr. Day", Rule::Nth(1, Weekday::Mon, 3)),
+    holiday("Washington's Birthday", Rule::Nth(2, Weekday::Mon, 3)),
+    holiday("Memorial Day", Rule::Last(5, Weekday::Mon)),
+    Holiday {
+        name: "Juneteenth",
+        rule: Rule::Fixed(19, 6),
+        since: 2021,
+    },
+    holiday("Independence Day", Rule::Fixed(4, 7)),
+    holiday("Labor Day", Rule::Nth(9, Weekday::Mon, 1)),
+    holiday("Columbus Day", Rule::Nth(10, Weekday::Mon, 2)),
+    holiday("Veterans Day", Rule::Fixed(11, 11)),
+    holiday("Thanksgiving Day", Rule::Nth(11, Weekday::Thu, 4)),
+    holiday("Christmas Day", Rule::Fixed(25, 12)),
+];
+
+// The national holidays only, not those of the individual states
+const GERMANY: [Holiday; 9] = [
+    holiday("New Year's Day", Rule::Fixed(1, 1)),
+    holiday("Good Friday", Rule::Easter(-2)),
+    holiday("Easter Monday", Rule::Easter(1)),
+    holiday("Labour Day", Rule::Fixed(1, 5)),
+    holiday("Ascension Day", Rule::Easter(39)),
+    holiday("Whit Monday", Rule::Easter(50)),
+    holiday("German Unity Day", Rule::Fixed(3, 10)),
+    holiday("Christmas Day", Rule::Fixed(25, 12)),
+    holiday("Second Day of Christmas", Rule::Fixed(26, 12)),
+];
+
+const FRANCE: [Holiday; 11] = [
+    holiday("New Year's Day", Rule::Fixed(1, 1)),
+    holiday("Easter Monday", Rule::Easter(1)),
+    holiday("Labour Day", Rule::Fixed(1, 5)),
+    holiday("Victory in Europe Day", Rule::Fixed(8, 5)),
+    holiday("Ascension Day", Rule::Easter(39)),
+    holiday("Whit Monday", Rule::Easter(50)),
+    holiday("Bastille Day", Rule::Fixed(14, 7)),
+    holiday("Assumption Day", Rule::Fixed(15, 8)),
+    holiday("All Saints' Day", Rule::Fixed(1, 11)),
+    holiday("Armistice Day", Rule::Fixed(11, 11)),
+    holiday("Christmas Day", Rule::Fixed(25, 12)),
+];
+
+fn region_rules(region: &str) -> Option<(&'static [Holiday], Weekend)> {
+    match region.to_uppercase().as_str() {
+        "GB-ENG" | "GB-WLS" => Some((&ENGLAND_AND_WALES, Weekend::Substitute)),
+        "GB-SCT" => Some((&SCOTLAND, Weekend::Substitute)),
+        "GB-NIR" => Some((&NORTHERN_IRELAND, Weekend::Substitute)),
+        "IE" => Some((&IRELAND, Weekend::Keep)),
+        "US" => Some((&UNITED_STATES, Weekend::Observe)),
+        "DE" => Some((&GERMANY, Weekend::Keep)),
+        "FR" => Some((&FRANCE, Weekend::Keep)),
+        _ => None,
+    }
+}
+
+// Easter Sunday in the Gregorian calendar, using the anonymous Gregorian algorithm
+fn easter(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+impl Rule {
+    fn date(&self, year: i32) -> NaiveDate {
+        match *self {
+            Rule::Fixed(day, month) => NaiveDate::from_ymd_opt(year, month, day).unwrap(),
+            Rule::Easter(days) => easter(year) + Duration::days(days),
+            Rule::Nth(month, weekday, n) => {
+                NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8).unwrap()
+            }
+            Rule::Last(month, weekday) => {
+                let mut date =
+                    NaiveDate::from_weekday_of_month_opt(year, month, weekday, 4).unwrap();
+                while (date + Duration::days(7)).month() == month {
+                    date += Duration::days(7);
+                }
+                date
+            }
+            Rule::FridayOrMonday(day, month) => {
+                let mut date = NaiveDate::from_ymd_opt(year, month, day).unwrap();
+                if date.weekday() == Weekday::Fri {
+                    return date;
+                }
+                while date.weekday() != Weekday::Mon {
+                    date = date.succ_opt().unwrap();
+                }
+                date
+            }
+        }
+    }
+
+    fn is_fixed(&self) -> bool {
+        matches!(self, Rule::Fixed(_, _))
+    }
+}
+
+// The public holidays in a region for a year, by date. Holidays that fall at the
+// weekend are kept along with the day taken instead
+pub fn holidays(region: &str, year: i32) -> Result<BTreeMap<NaiveDate, String>, String> {
+    let (rules, weekend) = region_rules(region).ok_or(format!(
+        "There is no holiday calendar for '{}', expected one of {}",
+        region,
+        REGIONS.join(", ")
+    ))?;
+
+    let mut holidays = BTreeMap::new();
+    let mut substitutes = vec![];
+    for holiday in rules.iter().filter(|holiday| year >= holiday.since) {
+        let date = holiday.rule.date(year);
+        holidays.insert(date, holiday.name.to_string());
+        if holiday.rule.is_fixed() && is_weekend(date) {
+            substitutes.push((date, holiday.name));
+        }
+    }
+
+    for (date, name) in substitutes {
+        let (substitute, name) = match weekend {
+            Weekend::Keep => continue,
+            Weekend::Observe if date.weekday() == Weekday::Sat => {
+                (date - Duration::days(1), format!("{} (observed)", name))
+            }
+            Weekend::Observe => (date + Duration::days(1), format!("{} (observed)", name)),
+            Weekend::Substitute => {
+                let mut substitute = date.succ_opt().unwrap();
+                while is_weekend(substitute) || holidays.contains_key(&substitute) {
+                    substitute = substitute.succ_opt().unwrap();
+                }
+                (substitute, format!("{} (substitute day)", name))
+            }
+        };
+        holidays.entry(substitute).or_insert(name);
+    }
+
+    Ok(holidays)
+}
+
+// What is done with commits on a day that isn't a working day e.g. "flag" or
+// { "overtime": 1.5 }. Days are counted like any other unless a policy is set
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OffDayPolicy {
+    // count the day like any other
+    #[default]
+    Include,
+    // leave the day out of the timesheet
+    Exclude,
+    // count the day, charged at the multiplier times the project's rate
+    Overtime(f64),
+    // count the day, marked for review before the timesheet is sent
+    Flag,
+}
+
+// How a day with commits on it is treated when the timesheet is built
+#[derive(PartialEq, Debug)]
+pub enum DayKind {
+    Working,
+    Off(DayOff),
+    Excluded,
+}
+
+// The days a project is worked on, kept in the config file with each project e.g.
+// { "working_days": ["Mon", "Tue", "Wed", "Thu"], "region": "GB-SCT",
+//   "holiday_policy": { "overtime": 2 } }
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct WorkingCalendar {
+    #[serde(default = "default_working_days")]
+    pub working_days: Vec<Weekday>,
+    // where public holidays are taken from, one of REGIONS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    // days off that aren't public holidays e.g. a company shutdown
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra_holidays: BTreeMap<NaiveDate, String>,
+    // for commits on days outside of the working week
+    #[serde(default)]
+    pub weekend_policy: OffDayPolicy,
+    #[serde(default)]
+    pub holiday_policy: OffDayPolicy,
+}
+
+fn default_working_days() -> Vec<Weekday> {
+    vec![
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+    ]
+}
+
+impl Default for WorkingCalendar {
+    fn default() -> WorkingCalendar {
+        WorkingCalendar {
+            working_days: default_working_days(),
+            region: None,
+            extra_holidays: BTreeMap::new(),
+            weekend_policy: OffDayPolicy::default(),
+            holiday_policy: OffDayPolicy::default(),
+        }
+    }
+}
+
+impl WorkingCalendar {
+    // The name of the holiday on a date, if it is one
+    pub fn holiday(&self, date: NaiveDate) -> Result<Option<String>, String> {
+        if let Some(name) = self.extra_holidays.get(&date) {
+            return Ok(Some(name.clone()));
+        }
+
+        match &self.region {
+            // a holiday observed on the 31st of December can belong to the next year
+            Some(region) => Ok(holidays(region, date.year())?.remove(&date).or(holidays(
+                region,
+                date.year() + 1,
+            )?
+            .remove(&date))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn is_working_day(&self, date: NaiveDate) -> Result<bool, String> {
+        Ok(self.working_days.contains(&date.weekday()) && self.holiday(date)?.is_none())
+    }
+
+    // Apply the policy for holidays and days outside of the working week to a date.
+    // A holiday takes precedence when it also falls outside of the working week
+    pub fn classify(&self, date: NaiveDate) -> Result<DayKind, String> {
+        let (reason, policy) = match self.holiday(date)? {
+            Some(name) => (name, &self.holiday_policy),
+            None if !self.working_days.contains(&date.weekday()) => {
+                (date.format("%A").to_string(), &self.weekend_policy)
+            }
+            None => return Ok(DayKind::Working),
+        };
+
+        Ok(match policy {
+            OffDayPolicy::Include => DayKind::Working,
+            OffDayPolicy::Exclude => DayKind::Excluded,
+            OffDayPolicy::Overtime(multiplier) => DayKind::Off(DayOff {
+                reason,
+                overtime: Some(*multiplier),
+                flagged: false,
+            }),
+            OffDayPolicy::Flag => DayKind::Off(DayOff {
+                reason,
+                overtime: None,
+                flagged: true,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn it_works_out_holidays_with_substitute_days() {
+        assert_eq!(easter(2024), date(2024, 3, 31));
+        assert_eq!(easter(2025), date(2025, 4, 20));
+
+        // Christmas 2021 was on a Saturday and Boxing Day on a Sunday
+        let england = holidays("GB-ENG", 2021).unwrap();
+        assert_eq!(england.len(), 10);
+        assert_eq!(england[&date(2021, 5, 31)], "Spring bank holiday");
+        assert_eq!(
+            england[&date(2021, 12, 27)],
+            "Christmas Day (substitute day)"
+        );
+        assert_eq!(england[&date(2021, 12, 28)], "Boxing Day (substitute day)");
+
+        let scotland = holidays("gb-sct", 2022).unwrap();
+        assert_eq!(
+            scotland[&date(2022, 1, 3)],
+            "New Year's Day (substitute day)"
+        );
+        assert_eq!(scotland[&date(2022, 1, 4)], "2nd January (substitute day)");
+        assert_eq!(scotland[&date(2022, 8, 1)], "Summer bank holiday");
+
+        // Independence Day 2026 is a Saturday, observed on the Friday
+        let us = holidays("US", 2026).unwrap();
+        assert_eq!(us[&date(2026, 7, 3)], "Independence Day (observed)");
+        assert_eq!(us[&date(2026, 11, 26)], "Thanksgiving Day");
+
+        assert_eq!(
+            holidays("IE", 2026).unwrap()[&date(2026, 2, 2)],
+            "St Brigid's Day"
+        );
+        assert!(holidays("XX", 2026).is_err());
+    }
+
+    #[test]
+    fn it_applies_the_policies_for_days_off() {
+        let mut calendar: WorkingCalendar = serde_json::from_str(
+            r#"{"working_days":["Mon","Tue","Wed","Thu"],"region":"GB-ENG",
+                "weekend_policy":"exclude","holiday_policy":{"overtime":2}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(calendar.classify(date(2021, 9, 1)), Ok(DayKind::Working));
+        assert_eq!(
+            WorkingCalendar::default().classify(date(2021, 9, 5)),
+            Ok(DayKind::Working)
+        );
+        assert_eq!(calendar.classify(date(2021, 9, 3)), Ok(DayKind::Excluded));
+        assert_eq!(
+            calendar.classify(date(2021, 12, 25)),
+            Ok(DayKind::Off(DayOff {
+                reason: "Christmas Day".to_string(),
+                overtime: Some(2.0),
+                flagged: false,
+            }))
+        );
+
+        calendar.weekend_policy = OffDayPolicy::Flag;
+        assert_eq!(
+            calendar.classify(date(2021, 9, 5)),
+            Ok(DayKind::Off(DayOff {
+                reason: "Sunday".to_string(),
+                overtime: None,
+                flagged: true,
+            }))
+        );
+    }
+}
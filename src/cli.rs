@@ -0,0 +1,359 @@
+use crate::export::Format;
+use crate::timesheet::EntryKind;
+use clap::{ArgGroup, Args, Parser, Subcommand};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Parser, PartialEq, Debug)]
+#[clap(
+    name = "timesheet-gen",
+    bin_name = "timesheet-gen",
+    version,
+    about = "Generate timesheets from your git history"
+)]
+pub struct Cli {
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum Command {
+    /// Initialise timesheet-gen for a repository, or add a repository to a project
+    #[clap(short_flag = 'i')]
+    Init(InitArgs),
+    /// Generate a timesheet for a project
+    #[clap(short_flag = 'm')]
+    Make(MakeArgs),
+    /// Print a timesheet as a calendar table, without publishing it
+    Show(ShowArgs),
+    /// Change the hours or note for a day, or add or remove a day
+    Edit(EditArgs),
+    /// Log time that doesn't produce commits, such as meetings and travel
+    Log(LogArgs),
+    /// Take days off as leave, such as vacation or sick days
+    Leave(LeaveArgs),
+    /// Issue an invoice for a project's timesheet, or list and re-render issued invoices
+    Invoice(InvoiceArgs),
+    /// Remove a published timesheet before it expires
+    Revoke(RevokeArgs),
+}
+
+#[derive(Args, PartialEq, Debug, Default)]
+pub struct InitArgs {
+    /// Path to the repository, defaults to the current directory
+    pub path: Option<String>,
+    /// Add the repository to an existing project rather than creating one
+    #[clap(long)]
+    pub add: bool,
+    #[clap(flatten)]
+    pub project: ProjectArgs,
+    /// Make the project the default for 'make'
+    #[clap(long)]
+    pub default: bool,
+    #[clap(flatten)]
+    pub client: ClientArgs,
+}
+
+#[derive(Args, PartialEq, Debug, Default)]
+pub struct MakeArgs {
+    #[clap(flatten)]
+    pub project: ProjectArgs,
+    #[clap(flatten)]
+    pub period: PeriodArgs,
+    /// Publish the timesheet to the web, or export it as a file
+    #[clap(long, arg_enum, default_value = "web")]
+    pub format: Format,
+    /// Review and edit the days in a full screen calendar first
+    #[clap(long)]
+    pub review: bool,
+    /// File to export to, defaults to stdout or a file named after the project for pdf
+    #[clap(long, short = 'o', value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, PartialEq, Debug, Default)]
+pub struct ShowArgs {
+    #[clap(flatten)]
+    pub project: ProjectArgs,
+    #[clap(flatten)]
+    pub period: PeriodArgs,
+    /// Print the table as plain text or markdown
+    #[clap(long, arg_enum, default_value = "text")]
+    pub format: Format,
+}
+
+#[derive(Args, PartialEq, Debug, Default)]
+#[clap(group(
+    ArgGroup::new("change")
+        .required(true)
+        .multiple(true)
+        .args(&["hours", "note", "add", "remove", "reset", "interactive"])
+))]
+pub struct EditArgs {
+    /// The day to edit, as YYYY-MM-DD
+    #[clap(value_name = "YYYY-MM-DD", required_unless_present = "interactive")]
+    pub date: Option<String>,
+    /// Edit a month in a full screen calendar
+    #[clap(
+        long,
+        short = 'i',
+        conflicts_with_all = &["date", "hours", "note", "add", "remove", "reset"]
+    )]
+    pub interactive: bool,
+    /// Month to edit interactively, as YYYY-MM. Defaults to the current month
+    #[clap(long, value_name = "YYYY-MM", requires = "interactive")]
+    pub month: Option<String>,
+    /// Hours worked on the day
+    #[clap(long)]
+    pub hours: Option<f64>,
+    /// Note shown against the day
+    #[clap(long)]
+    pub note: Option<String>,
+    /// Add the day even though there are no commits on it
+    #[clap(long, requires = "hours")]
+    pub add: bool,
+    /// Leave the day out of the timesheet
+    #[clap(long, conflicts_with_all = &["hours", "note", "add"])]
+    pub remove: bool,
+    /// Discard any edits to the day
+    #[clap(long, conflicts_with_all = &["hours", "note", "add", "remove"])]
+    pub reset: bool,
+    #[clap(flatten)]
+    pub project: ProjectArgs,
+}
+
+#[derive(Args, PartialEq, Debug)]
+pub struct LogArgs {
+    /// The day the time was spent, as YYYY-MM-DD
+    #[clap(long, value_name = "YYYY-MM-DD")]
+    pub date: String,
+    /// Hours spent
+    #[clap(long)]
+    pub hours: f64,
+    /// What the time was spent on
+    #[clap(long, possible_values = ENTRY_KINDS.map(|(name, _)| name))]
+    pub kind: EntryKind,
+    /// Note shown against the day
+    #[clap(long)]
+    pub note: Option<String>,
+    #[clap(flatten)]
+    pub project: ProjectArgs,
+}
+
+#[derive(Args, PartialEq, Debug)]
+pub struct LeaveArgs {
+    /// First day of leave, as YYYY-MM-DD
+    #[clap(long, value_name = "YYYY-MM-DD")]
+    pub from: String,
+    /// Last day of leave, as YYYY-MM-DD. Defaults to the first day
+    #[clap(long, value_name = "YYYY-MM-DD")]
+    pub to: Option<String>,
+    /// The kind of leave
+    #[clap(long, default_value = "vacation", possible_values = ENTRY_KINDS.map(|(name, _)| name))]
+    pub kind: EntryKind,
+    /// Note shown against each day
+    #[clap(long)]
+    pub note: Option<String>,
+    #[clap(flatten)]
+    pub project: ProjectArgs,
+}
+
+#[derive(Args, PartialEq, Debug, Default)]
+pub struct InvoiceArgs {
+    #[clap(flatten)]
+    pub project: ProjectArgs,
+    #[clap(flatten)]
+    pub period: PeriodArgs,
+    /// List the invoices issued so far
+    #[clap(long, conflicts_with_all = &["render", "month", "from", "to", "previous"])]
+    pub list: bool,
+    /// Render an issued invoice again, by its reference e.g. INV-0001
+    #[clap(long, value_name = "REFERENCE", conflicts_with_all = &["month", "from", "to", "previous"])]
+    pub render: Option<String>,
+    /// Directory to write the invoice PDF and JSON to, defaults to the current directory
+    #[clap(long, short = 'o', value_name = "DIR")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, PartialEq, Debug, Default)]
+pub struct RevokeArgs {
+    /// The random path the timesheet was published at
+    pub path: String,
+    #[clap(flatten)]
+    pub project: ProjectArgs,
+}
+
+#[derive(Args, PartialEq, Debug, Default)]
+pub struct ProjectArgs {
+    /// Name of the project, defaults to the default project
+    #[clap(long, value_name = "NAME")]
+    pub project: Option<String>,
+}
+
+#[derive(Args, PartialEq, Debug, Default)]
+pub struct ClientArgs {
+    /// Name of the client, skips the client prompts
+    #[clap(long, value_name = "NAME")]
+    pub client_name: Option<String>,
+    /// Name of the contact person at the client
+    #[clap(long, value_name = "NAME")]
+    pub contact: Option<String>,
+    /// Address of the client, comma separated
+    #[clap(long)]
+    pub address: Option<String>,
+    /// Answer yes to every prompt, for running without input e.g. in CI
+    #[clap(long, short = 'y')]
+    pub yes: bool,
+}
+
+#[derive(Args, PartialEq, Debug, Default)]
+pub struct PeriodArgs {
+    /// Month to generate the timesheet for, as YYYY-MM. Defaults to the current month
+    #[clap(long, value_name = "YYYY-MM", conflicts_with_all = &["from", "to", "previous"])]
+    pub month: Option<String>,
+    /// First day to include, as YYYY-MM-DD
+    #[clap(long, value_name = "YYYY-MM-DD", requires = "to")]
+    pub from: Option<String>,
+    /// Last day to include, as YYYY-MM-DD
+    #[clap(long, value_name = "YYYY-MM-DD", requires = "from")]
+    pub to: Option<String>,
+    /// Generate the timesheet for the previous month
+    #[clap(long, conflicts_with_all = &["from", "to"])]
+    pub previous: bool,
+}
+
+// The names --kind takes, the same as in the config file
+const ENTRY_KINDS: [(&str, EntryKind); 9] = [
+    ("meeting", EntryKind::Meeting),
+    ("workshop", EntryKind::Workshop),
+    ("travel", EntryKind::Travel),
+    ("admin", EntryKind::Admin),
+    ("other", EntryKind::Other),
+    ("vacation", EntryKind::Vacation),
+    ("sick", EntryKind::Sick),
+    ("parental", EntryKind::Parental),
+    ("unpaid", EntryKind::Unpaid),
+];
+
+impl FromStr for EntryKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<EntryKind, String> {
+        ENTRY_KINDS
+            .iter()
+            .find(|(kind_name, _)| *kind_name == name)
+            .map(|(_, kind)| *kind)
+            .ok_or_else(|| format!("'{}' isn't a kind of time", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn it_has_a_valid_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn it_parses_non_interactive_init() {
+        let cli = Cli::try_parse_from([
+            "timesheet-gen",
+            "init",
+            "/path/to/repo",
+            "--project",
+            "acme",
+            "--client-name",
+            "Acme Corp",
+            "--address",
+            "1 Road, Town",
+            "--yes",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Command::Init(args) => {
+                assert_eq!(args.path, Some("/path/to/repo".to_string()));
+                assert_eq!(args.project.project, Some("acme".to_string()));
+                assert_eq!(args.client.client_name, Some("Acme Corp".to_string()));
+                assert!(args.client.yes);
+            }
+            _ => panic!("Wrong command parsed"),
+        }
+    }
+
+    #[test]
+    fn it_rejects_conflicting_periods() {
+        assert!(Cli::try_parse_from(["timesheet-gen", "-m", "--month", "2021-09"]).is_ok());
+        assert!(
+            Cli::try_parse_from(["timesheet-gen", "make", "--month", "2021-09", "--previous"])
+                .is_err()
+        );
+        assert!(Cli::try_parse_from(["timesheet-gen", "make", "--from", "2021-09-01"]).is_err());
+        assert!(
+            Cli::try_parse_from(["timesheet-gen", "invoice", "--list", "--month", "2021-09"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn it_requires_a_change_to_edit() {
+        let edit = |args: &[&str]| {
+            Cli::try_parse_from(["timesheet-gen", "edit", "2021-09-14"].iter().chain(args))
+        };
+
+        assert!(edit(&["--hours", "5.5", "--note", "client workshop"]).is_ok());
+        assert!(edit(&["--add", "--hours", "2"]).is_ok());
+        assert!(edit(&["--remove"]).is_ok());
+        assert!(edit(&[]).is_err());
+        assert!(edit(&["--add"]).is_err());
+        assert!(edit(&["--remove", "--hours", "2"]).is_err());
+        assert!(Cli::try_parse_from(["timesheet-gen", "edit", "-i", "--month", "2021-09"]).is_ok());
+        assert!(Cli::try_parse_from(["timesheet-gen", "edit", "--month", "2021-09"]).is_err());
+    }
+
+    #[test]
+    fn it_parses_time_logged_by_hand() {
+        let cli = Cli::try_parse_from([
+            "timesheet-gen",
+            "log",
+            "--date",
+            "2021-09-10",
+            "--hours",
+            "2",
+            "--kind",
+            "meeting",
+            "--note",
+            "sprint planning",
+        ])
+        .unwrap();
+        match cli.command {
+            Command::Log(args) => assert_eq!(args.kind, EntryKind::Meeting),
+            _ => panic!("Wrong command parsed"),
+        }
+
+        let cli = Cli::try_parse_from(["timesheet-gen", "leave", "--from", "2021-09-13"]).unwrap();
+        match cli.command {
+            Command::Leave(args) => assert_eq!(args.kind, EntryKind::Vacation),
+            _ => panic!("Wrong command parsed"),
+        }
+        assert!(Cli::try_parse_from(["timesheet-gen", "log", "--date", "2021-09-10"]).is_err());
+        assert!(Cli::try_parse_from([
+            "timesheet-gen",
+            "leave",
+            "--from",
+            "2021-09-13",
+            "--kind",
+            "holiday"
+        ])
+        .is_err());
+        for (name, kind) in ENTRY_KINDS.iter() {
+            assert_eq!(
+                serde_json::to_string(kind).unwrap(),
+                format!("\"{}\"", name)
+            );
+        }
+    }
+}
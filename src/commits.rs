@@ -0,0 +1,1193 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, TimeZone};
+use chrono_tz::Tz;
+use git2::{DiffOptions, Oid, Repository, Sort};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::Path;
+
+// A single commit read from the repository, holding the author details and the
+// author and committer timestamps in the offsets they were originally recorded with
+#[derive(PartialEq, Debug, Clone)]
+pub struct Commit {
+    pub id: String,
+    pub author_name: String,
+    pub author_email: String,
+    pub author_time: DateTime<FixedOffset>,
+    // Rewritten when the commit is rebased, cherry-picked or amended
+    pub committer_time: DateTime<FixedOffset>,
+    pub summary: String,
+    pub message: String,
+    pub is_merge: bool,
+    // The branches the commit is on that haven't been merged into the mainline
+    pub branches: Vec<String>,
+}
+
+// One of the names and/or emails a developer commits with. Every field that is
+// set has to match, with pattern being a regex matched against "Name <email>"
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Identity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+}
+
+impl Identity {
+    pub fn from_email(email: &str) -> Identity {
+        Identity {
+            email: Some(email.to_string()),
+            ..Default::default()
+        }
+    }
+
+    // Matches the way `git log --author` does, anywhere in "Name <email>", but
+    // ignoring case
+    pub fn from_author(author: &str) -> Identity {
+        Identity {
+            pattern: Some(format!("(?i){}", regex::escape(author))),
+            ..Default::default()
+        }
+    }
+}
+
+// The compiled form of a list of identities, matching a commit made by any of them
+struct AuthorMatcher<'a> {
+    identities: Vec<(&'a Identity, Option<Regex>)>,
+}
+
+impl AuthorMatcher<'_> {
+    fn new(identities: &[Identity]) -> Result<AuthorMatcher<'_>, regex::Error> {
+        let mut compiled = vec![];
+        for identity in identities {
+            let pattern = match &identity.pattern {
+                Some(pattern) => Some(Regex::new(pattern)?),
+                None => None,
+            };
+            compiled.push((identity, pattern));
+        }
+
+        Ok(AuthorMatcher {
+            identities: compiled,
+        })
+    }
+
+    fn matches(&self, commit: &Commit) -> bool {
+        let signature = format!("{} <{}>", commit.author_name, commit.author_email);
+
+        self.identities.iter().any(|(identity, pattern)| {
+            let name_matches = identity
+                .name
+                .as_ref()
+                .is_none_or(|name| name == &commit.author_name);
+            let email_matches = identity
+                .email
+                .as_ref()
+                .is_none_or(|email| email.eq_ignore_ascii_case(&commit.author_email));
+            let pattern_matches = pattern
+                .as_ref()
+                .is_none_or(|pattern| pattern.is_match(&signature));
+
+            let has_criteria =
+                identity.name.is_some() || identity.email.is_some() || pattern.is_some();
+
+            has_criteria && name_matches && email_matches && pattern_matches
+        })
+    }
+}
+
+fn signature_time(time: git2::Time) -> Result<DateTime<FixedOffset>, Box<dyn Error>> {
+    let offset = FixedOffset::east_opt(time.offset_minutes() * 60)
+        .ok_or("Commit has an invalid timezone offset")?;
+    Ok(offset
+        .timestamp_opt(time.seconds(), 0)
+        .single()
+        .ok_or("Commit has an invalid timestamp")?)
+}
+
+impl Commit {
+    // The author is resolved through the repository's .mailmap, so commits made
+    // under an old name or email are attributed to the canonical identity
+    fn from_git2(commit: &git2::Commit, mailmap: &git2::Mailmap) -> Result<Commit, Box<dyn Error>> {
+        let author = commit.author_with_mailmap(mailmap)?;
+
+        Ok(Commit {
+            id: commit.id().to_string(),
+            author_name: String::from_utf8_lossy(author.name_bytes()).to_string(),
+            author_email: String::from_utf8_lossy(author.email_bytes()).to_string(),
+            author_time: signature_time(author.when())?,
+            committer_time: signature_time(commit.committer().when())?,
+            summary: commit.summary().unwrap_or("").to_string(),
+            message: String::from_utf8_lossy(commit.message_bytes()).to_string(),
+            is_merge: commit.parent_count() > 1,
+            branches: vec![],
+        })
+    }
+
+    // More than a day between the author and committer dates, usually from a rebase
+    // or cherry-pick, so the day the commit counts toward depends on which is used
+    pub fn dates_differ(&self) -> bool {
+        (self.author_time - self.committer_time).num_seconds().abs() > 24 * 60 * 60
+    }
+}
+
+// Lists rebased or cherry-picked commits, which could be on a different day
+// depending on whether the author or committer date is used
+pub fn rewritten_commits_warning(commits: &[Commit], source: TimeSource) -> Option<String> {
+    if commits.is_empty() {
+        return None;
+    }
+
+    let mut warning = format!(
+        "Warning: these commits have author and committer dates more than a day apart, the {} date is used",
+        match source {
+            TimeSource::Author => "author",
+            TimeSource::Committer => "committer",
+        }
+    );
+    for commit in commits {
+        warning.push_str(&format!(
+            "\n  {} authored {}, committed {}: {}",
+            &commit.id[..7.min(commit.id.len())],
+            commit.author_time.format("%a %-d %b %Y"),
+            commit.committer_time.format("%a %-d %b %Y"),
+            commit.summary
+        ));
+    }
+    Some(warning)
+}
+
+// How the note for each day is built from its commit subjects, e.g.
+// { "from_commits": true, "max_length": 120 }
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct NotesConfig {
+    #[serde(default = "from_commits_default")]
+    pub from_commits: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+}
+
+fn from_commits_default() -> bool {
+    true
+}
+
+impl Default for NotesConfig {
+    fn default() -> NotesConfig {
+        NotesConfig {
+            from_commits: true,
+            max_length: None,
+        }
+    }
+}
+
+// Which of a commit's timestamps places it on a day
+#[derive(PartialEq, Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeSource {
+    #[default]
+    Author,
+    Committer,
+}
+
+// How commit times are bucketed into days e.g.
+// { "timezone": "Europe/Berlin", "day_rollover_hour": 4, "source": "committer" }
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimeConfig {
+    // The IANA timezone that days are reported in. Without one, each commit's
+    // own offset is used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<Tz>,
+    // Work before this hour counts toward the day before, so that a late night
+    // isn't split across two days
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub day_rollover_hour: u32,
+    #[serde(default)]
+    pub source: TimeSource,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+impl TimeConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.day_rollover_hour {
+            0..=23 => Ok(()),
+            hour => Err(format!("day_rollover_hour must be 0 to 23, not {}", hour)),
+        }
+    }
+
+    pub fn commit_time(&self, commit: &Commit) -> DateTime<FixedOffset> {
+        match self.source {
+            TimeSource::Author => commit.author_time,
+            TimeSource::Committer => commit.committer_time,
+        }
+    }
+
+    // The day a commit made at the given time counts toward
+    pub fn day_of(&self, time: &DateTime<FixedOffset>) -> NaiveDate {
+        let local = match &self.timezone {
+            Some(timezone) => time.with_timezone(timezone).naive_local(),
+            None => time.naive_local(),
+        };
+        (local - Duration::hours(self.day_rollover_hour as i64)).date()
+    }
+}
+
+// Commits that only tidy up history and say nothing about the work done
+fn is_noise(commit: &Commit) -> bool {
+    let summary = commit.summary.trim_start();
+    commit.is_merge
+        || summary.starts_with("Merge ")
+        || ["fixup!", "squash!", "amend!"]
+            .iter()
+            .any(|prefix| summary.starts_with(prefix))
+}
+
+// The subjects of a day's commits in the order they were made, by the time
+// they're bucketed into days with, without merges, fixups or repeats, joined
+// into a single note
+pub fn summarise(commits: &[Commit], config: &NotesConfig, time: &TimeConfig) -> Option<String> {
+    if !config.from_commits {
+        return None;
+    }
+
+    let mut commits: Vec<&Commit> = commits.iter().filter(|commit| !is_noise(commit)).collect();
+    commits.sort_by_key(|commit| time.commit_time(commit));
+
+    let mut seen = HashSet::new();
+    let subjects: Vec<&str> = commits
+        .iter()
+        .map(|commit| commit.summary.trim())
+        .filter(|summary| !summary.is_empty() && seen.insert(summary.to_lowercase()))
+        .collect();
+    if subjects.is_empty() {
+        return None;
+    }
+
+    let note = subjects.join("; ");
+    match config.max_length {
+        Some(max_length) if note.chars().count() > max_length => {
+            let truncated: String = note.chars().take(max_length.saturating_sub(1)).collect();
+            Some(format!("{}…", truncated.trim_end()))
+        }
+        _ => Some(note),
+    }
+}
+
+// The subjects git, GitHub and GitLab give merge commits e.g.
+// "Merge branch 'feature/ABC-12-login' into main" or
+// "Merge pull request #12 from someone/feature/ABC-12-login"
+const MERGE_SUBJECT: &str = concat!(
+    r"^Merge (?:branch '(?P<branch>[^']+)'",
+    r"|remote-tracking branch '[^/']+/(?P<remote>[^']+)'",
+    r"|pull request #\d+ from [^/\s]+/(?P<request>\S+))"
+);
+
+// The branch a merge commit brought in
+fn merged_branch(merge_subject: &Regex, summary: &str) -> Option<String> {
+    let captures = merge_subject.captures(summary)?;
+    ["branch", "remote", "request"]
+        .iter()
+        .find_map(|name| captures.name(name))
+        .map(|name| name.as_str().to_string())
+}
+
+// The branches each commit was made on. Merged branches are named from their merge
+// commit, so the names stay the same after the branch is merged or deleted, and
+// the branch a commit was first merged from is the one it keeps. Branches that are
+// still open are walked back to where they left the mainline, but only those the
+// filter reads from. Without a mainline every commit would be on every open
+// branch, so those are left out
+fn branches_by_commit(
+    repository: &Repository,
+    filter: &CommitFilter,
+    tips: &[Oid],
+) -> Result<HashMap<Oid, Vec<String>>, Box<dyn Error>> {
+    let mut branches: HashMap<Oid, Vec<String>> = HashMap::new();
+    let merge_subject = Regex::new(MERGE_SUBJECT)?;
+
+    let mut revwalk = repository.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    for tip in tips {
+        revwalk.push(*tip)?;
+    }
+    for oid in revwalk {
+        let merge = repository.find_commit(oid?)?;
+        if merge.parent_count() < 2 {
+            continue;
+        }
+        let name = match merged_branch(&merge_subject, merge.summary().unwrap_or("")) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let mut merged = repository.revwalk()?;
+        for parent in merge.parent_ids().skip(1) {
+            merged.push(parent)?;
+        }
+        merged.hide(merge.parent_id(0)?)?;
+        for oid in merged {
+            branches.entry(oid?).or_insert_with(|| vec![name.clone()]);
+        }
+    }
+
+    let mainline = match filter.mainline(repository)? {
+        Some(mainline) => mainline,
+        None => return Ok(branches),
+    };
+
+    let merged: HashSet<Oid> = branches.keys().copied().collect();
+    let refs = filter.refs(repository)?;
+    for branch in repository.branches(None)? {
+        let (branch, _) = branch?;
+        if !branch.get().name().is_some_and(|name| refs.contains(name)) {
+            continue;
+        }
+        // symbolic refs such as origin/HEAD have no target of their own
+        let (name, tip) = match (branch.name()?, branch.get().target()) {
+            (Some(name), Some(tip)) => (name.to_string(), tip),
+            _ => continue,
+        };
+
+        let mut revwalk = repository.revwalk()?;
+        revwalk.push(tip)?;
+        revwalk.hide(mainline)?;
+        for oid in revwalk {
+            let oid = oid?;
+            if !merged.contains(&oid) {
+                branches.entry(oid).or_default().push(name.clone());
+            }
+        }
+    }
+
+    Ok(branches)
+}
+
+// Every local and remote branch, which leaves out the stash and notes
+const DEFAULT_REFS: [&str; 2] = ["refs/heads/*", "refs/remotes/*"];
+
+// Which commits are read from one of a project's repositories e.g.
+// { "include_refs": ["refs/heads/*", "refs/remotes/origin/main"], "skip_merges": true }
+// Patterns are matched against full ref names, and every local and remote branch
+// is read when no include patterns are given
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommitFilter {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_refs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_refs: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub skip_merges: bool,
+    // Globs for the parts of a monorepo that belong to the project e.g.
+    // ["services/acme/**"], only commits changing a matching file count
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    // The ref that branches are made from and merged back into e.g.
+    // "refs/heads/develop", otherwise a remote's default branch or HEAD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mainline: Option<String>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl CommitFilter {
+    fn matching_refs(
+        repository: &Repository,
+        patterns: &[String],
+    ) -> Result<HashSet<String>, Box<dyn Error>> {
+        let mut names = HashSet::new();
+        for pattern in patterns {
+            for reference in repository.references_glob(pattern)? {
+                if let Some(name) = reference?.name() {
+                    names.insert(name.to_string());
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    // The names of every ref that is included and not excluded. An include
+    // pattern that matches nothing is most likely a mistake, which would
+    // otherwise leave the timesheet empty
+    fn refs(&self, repository: &Repository) -> Result<HashSet<String>, Box<dyn Error>> {
+        if self.include_refs.is_empty() {
+            let patterns: Vec<String> = DEFAULT_REFS.iter().map(|glob| glob.to_string()).collect();
+            let names = CommitFilter::matching_refs(repository, &patterns)?;
+            return self.without_excluded(repository, names);
+        }
+
+        let mut names = HashSet::new();
+        for pattern in self.include_refs.iter() {
+            let matching = CommitFilter::matching_refs(repository, std::slice::from_ref(pattern))?;
+            if matching.is_empty() {
+                return Err(format!(
+                    "No refs in {} match '{}'",
+                    repository.path().display(),
+                    pattern
+                )
+                .into());
+            }
+            names.extend(matching);
+        }
+        self.without_excluded(repository, names)
+    }
+
+    fn without_excluded(
+        &self,
+        repository: &Repository,
+        names: HashSet<String>,
+    ) -> Result<HashSet<String>, Box<dyn Error>> {
+        let excluded = CommitFilter::matching_refs(repository, &self.exclude_refs)?;
+        Ok(names
+            .into_iter()
+            .filter(|name| !excluded.contains(name))
+            .collect())
+    }
+
+    // The commit the mainline is at. A remote's HEAD is its default branch,
+    // whichever branch is checked out otherwise
+    fn mainline(&self, repository: &Repository) -> Result<Option<Oid>, Box<dyn Error>> {
+        if let Some(name) = &self.mainline {
+            return match repository.refname_to_id(name) {
+                Ok(oid) => Ok(Some(oid)),
+                Err(_) => Err(format!(
+                    "The mainline '{}' isn't a ref in {}",
+                    name,
+                    repository.path().display()
+                )
+                .into()),
+            };
+        }
+
+        let remotes = repository.remotes()?;
+        let remote_head = remotes.iter().flatten().find_map(|remote| {
+            repository
+                .refname_to_id(&format!("refs/remotes/{}/HEAD", remote))
+                .ok()
+        });
+        Ok(remote_head.or_else(|| repository.head().ok().and_then(|head| head.target())))
+    }
+
+    // The commits at the tip of every ref the filter reads from
+    fn tips(&self, repository: &Repository) -> Result<Vec<Oid>, Box<dyn Error>> {
+        let mut tips = vec![];
+        for name in self.refs(repository)? {
+            // tags can point at trees and blobs as well as commits
+            if let Ok(commit) = repository.find_reference(&name)?.peel_to_commit() {
+                tips.push(commit.id());
+            }
+        }
+
+        // HEAD may be detached, or unborn in an empty repository
+        if self.include_refs.is_empty() {
+            if let Ok(head) = repository.head() {
+                tips.extend(head.target());
+            }
+        }
+        Ok(tips)
+    }
+}
+
+// The patch id of a commit's changes (as `git patch-id` gives), which is the same
+// for a commit and its cherry-picks. Merges and commits without changes have none
+fn patch_id(repository: &Repository, commit: &git2::Commit) -> Result<Option<Oid>, Box<dyn Error>> {
+    if commit.parent_count() > 1 {
+        return Ok(None);
+    }
+
+    let parent_tree = match commit.parent_count() {
+        0 => None,
+        _ => Some(commit.parent(0)?.tree()?),
+    };
+    let diff = repository.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    if diff.deltas().len() == 0 {
+        return Ok(None);
+    }
+    Ok(Some(diff.patchid(None)?))
+}
+
+// Whether the commit changes any file matching the paths. Like `git log -- <paths>`,
+// a merge only counts if it differs from every parent, so the changes it brings
+// in are counted on the commits that made them
+fn touches_paths(
+    repository: &Repository,
+    commit: &git2::Commit,
+    paths: &[String],
+) -> Result<bool, Box<dyn Error>> {
+    let mut options = DiffOptions::new();
+    for path in paths {
+        options.pathspec(path);
+    }
+
+    let tree = commit.tree()?;
+    if commit.parent_count() == 0 {
+        let diff = repository.diff_tree_to_tree(None, Some(&tree), Some(&mut options))?;
+        return Ok(diff.deltas().len() > 0);
+    }
+    for parent in commit.parents() {
+        let diff =
+            repository.diff_tree_to_tree(Some(&parent.tree()?), Some(&tree), Some(&mut options))?;
+        if diff.deltas().len() == 0 {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// Walk the refs the filter allows (by default every branch, so not the stash or
+// notes that `git log --all` would read) and return the commits made by any of the given identities
+// that touch the filter's paths, newest first. A patch on several branches is
+// only returned once, as the first commit that made it. Finding the branches
+// commits were made on means walking every merge, so it is only done on request
+pub fn collect_commits(
+    path: &Path,
+    identities: &[Identity],
+    filter: &CommitFilter,
+    with_branches: bool,
+) -> Result<Vec<Commit>, Box<dyn Error>> {
+    let authors = AuthorMatcher::new(identities)?;
+    let repository = Repository::open(path)?;
+    let mailmap = repository.mailmap()?;
+    let mut revwalk = repository.revwalk()?;
+    revwalk.set_sorting(Sort::TIME | Sort::REVERSE)?;
+    let tips = filter.tips(&repository)?;
+    for tip in tips.iter() {
+        revwalk.push(*tip)?;
+    }
+
+    let mut branches = match with_branches {
+        true => branches_by_commit(&repository, filter, &tips)?,
+        false => HashMap::new(),
+    };
+    let mut patches = HashSet::new();
+    let mut commits = vec![];
+    for oid in revwalk {
+        let oid = oid?;
+        let git_commit = repository.find_commit(oid)?;
+        if filter.skip_merges && git_commit.parent_count() > 1 {
+            continue;
+        }
+
+        let mut commit = Commit::from_git2(&git_commit, &mailmap)?;
+        if !authors.matches(&commit) {
+            continue;
+        }
+        if !filter.paths.is_empty() && !touches_paths(&repository, &git_commit, &filter.paths)? {
+            continue;
+        }
+        if let Some(patch_id) = patch_id(&repository, &git_commit)? {
+            if !patches.insert(patch_id) {
+                continue;
+            }
+        }
+
+        commit.branches = branches.remove(&oid).unwrap_or_default();
+        commits.push(commit);
+    }
+
+    commits.reverse();
+    Ok(commits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+    use git2::{Signature, Time};
+    use std::path::PathBuf;
+
+    fn create_repository_with_commits(authors: &[(&str, &str, i64, i32)]) -> PathBuf {
+        let path = std::env::temp_dir().join(utils::generate_random_path());
+        let repository = Repository::init(&path).unwrap();
+        let tree_id = repository.index().unwrap().write_tree().unwrap();
+        let tree = repository.find_tree(tree_id).unwrap();
+
+        let mut parent: Option<git2::Oid> = None;
+        for (name, email, seconds, offset) in authors {
+            let signature = Signature::new(name, email, &Time::new(*seconds, *offset)).unwrap();
+            let parents = match parent {
+                Some(oid) => vec![repository.find_commit(oid).unwrap()],
+                None => vec![],
+            };
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            parent = Some(
+                repository
+                    .commit(
+                        Some("HEAD"),
+                        &signature,
+                        &signature,
+                        "A commit",
+                        &tree,
+                        &parents,
+                    )
+                    .unwrap(),
+            );
+        }
+
+        path
+    }
+
+    #[test]
+    fn it_collects_commits_for_an_author_with_their_original_offset() {
+        let path = create_repository_with_commits(&[
+            ("Tom Jones", "sex_bomb@gmail.com", 1633305600, 60),
+            ("Someone Else", "someone@else.com", 1633392000, 0),
+            ("Tom Jones", "sex_bomb@gmail.com", 1633478400, -300),
+        ]);
+
+        let commits = collect_commits(
+            &path,
+            &[Identity::from_author("Tom Jones")],
+            &CommitFilter::default(),
+            false,
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+
+        assert_eq!(commits.len(), 2);
+        assert_eq!(
+            commits[0].author_time.to_rfc3339(),
+            "2021-10-05T19:00:00-05:00"
+        );
+        assert_eq!(
+            commits[1].author_time.to_rfc3339(),
+            "2021-10-04T01:00:00+01:00"
+        );
+        assert!(!commits[1].dates_differ());
+        assert_eq!(commits[1].summary, "A commit");
+    }
+
+    #[test]
+    fn it_collects_nothing_from_an_empty_repository() {
+        let path = create_repository_with_commits(&[]);
+        let commits = collect_commits(
+            &path,
+            &[Identity::from_author("Tom Jones")],
+            &CommitFilter::default(),
+            false,
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+
+        assert!(commits.is_empty());
+    }
+
+    #[test]
+    fn it_matches_any_identity_after_applying_the_mailmap() {
+        let path = create_repository_with_commits(&[
+            ("Tom Jones", "sex_bomb@gmail.com", 1633305600, 0),
+            ("tj", "tom@laptop.local", 1633392000, 0),
+            ("Tom Jones", "tom@work.com", 1633478400, 0),
+            ("Someone Else", "someone@else.com", 1633564800, 0),
+        ]);
+        std::fs::write(
+            path.join(".mailmap"),
+            "Tom Jones <sex_bomb@gmail.com> tj <tom@laptop.local>\n",
+        )
+        .unwrap();
+
+        let identities = vec![
+            Identity::from_email("SEX_BOMB@gmail.com"),
+            Identity {
+                pattern: Some(r"@work\.com>$".to_string()),
+                ..Default::default()
+            },
+        ];
+        let commits = collect_commits(&path, &identities, &CommitFilter::default(), false).unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+
+        let emails: Vec<&str> = commits
+            .iter()
+            .map(|commit| commit.author_email.as_str())
+            .collect();
+        assert_eq!(
+            emails,
+            vec!["tom@work.com", "sex_bomb@gmail.com", "sex_bomb@gmail.com"]
+        );
+    }
+
+    #[test]
+    fn it_matches_the_configured_author_loosely_without_identities() {
+        let path = create_repository_with_commits(&[
+            ("Tom Jones", "Sex_Bomb@GMail.com", 1633305600, 0),
+            ("tom jones", "tom@work.com", 1633392000, 0),
+            ("Tom Jones (laptop)", "tom@laptop.local", 1633478400, 0),
+            ("tj", "SEX_BOMB@gmail.com", 1633564800, 0),
+            ("Someone Else", "someone@else.com", 1633651200, 0),
+        ]);
+        let repo = crate::repo::Repo {
+            name: "Tom Jones".to_string(),
+            email: "sex_bomb@gmail.com".to_string(),
+            ..Default::default()
+        };
+
+        let commits = collect_commits(
+            &path,
+            &repo.author_identities(),
+            &CommitFilter::default(),
+            false,
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+
+        let names: Vec<&str> = commits
+            .iter()
+            .map(|commit| commit.author_name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["tj", "Tom Jones (laptop)", "tom jones", "Tom Jones"]
+        );
+    }
+
+    #[test]
+    fn it_leaves_out_stashed_changes() {
+        let path =
+            create_repository_with_commits(&[("Tom Jones", "sex_bomb@gmail.com", 1633305600, 0)]);
+        let mut repository = Repository::open(&path).unwrap();
+        std::fs::write(path.join("wip.txt"), "not done yet").unwrap();
+        let signature =
+            Signature::new("Tom Jones", "sex_bomb@gmail.com", &Time::new(1633392000, 0)).unwrap();
+        repository
+            .stash_save(
+                &signature,
+                "WIP on login",
+                Some(git2::StashFlags::INCLUDE_UNTRACKED),
+            )
+            .unwrap();
+        assert!(repository.refname_to_id("refs/stash").is_ok());
+
+        let commits = collect_commits(
+            &path,
+            &[Identity::from_author("Tom Jones")],
+            &CommitFilter::default(),
+            false,
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary, "A commit");
+    }
+
+    #[test]
+    fn it_finds_the_branches_that_havent_been_merged() {
+        let path = create_repository_with_commits(&[
+            ("Tom Jones", "sex_bomb@gmail.com", 1633305600, 0),
+            ("Tom Jones", "sex_bomb@gmail.com", 1633392000, 0),
+        ]);
+        let repository = Repository::open(&path).unwrap();
+        let mainline = repository.head().unwrap().peel_to_commit().unwrap();
+        let signature =
+            Signature::new("Tom Jones", "sex_bomb@gmail.com", &Time::new(1633478400, 0)).unwrap();
+        repository
+            .commit(
+                Some("refs/heads/feature/ABC-12-login"),
+                &signature,
+                &signature,
+                "Add login",
+                &mainline.tree().unwrap(),
+                &[&mainline],
+            )
+            .unwrap();
+        // the mainline is whatever HEAD is on, not a branch with a known name
+        let head = repository.head().unwrap().shorthand().unwrap().to_string();
+        repository
+            .find_branch(&head, git2::BranchType::Local)
+            .unwrap()
+            .rename("trunk", false)
+            .unwrap();
+
+        let commits = collect_commits(
+            &path,
+            &[Identity::from_author("Tom Jones")],
+            &CommitFilter::default(),
+            true,
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+
+        assert_eq!(commits.len(), 3);
+        assert_eq!(commits[0].summary, "Add login");
+        assert_eq!(commits[0].branches, vec!["feature/ABC-12-login"]);
+        assert!(commits[1].branches.is_empty());
+    }
+
+    #[test]
+    fn it_filters_refs_and_merges_and_skips_repeated_patches() {
+        let path = std::env::temp_dir().join(utils::generate_random_path());
+        let repository = Repository::init(&path).unwrap();
+        let commit = |refname: Option<&str>, files: &[&str], seconds: i64, parents: &[Oid]| {
+            let mut builder = repository.treebuilder(None).unwrap();
+            for file in files {
+                let blob = repository.blob(file.as_bytes()).unwrap();
+                builder.insert(file, blob, 0o100644).unwrap();
+            }
+            let tree = repository.find_tree(builder.write().unwrap()).unwrap();
+            let parents: Vec<git2::Commit> = parents
+                .iter()
+                .map(|oid| repository.find_commit(*oid).unwrap())
+                .collect();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            let signature =
+                Signature::new("Tom Jones", "sex_bomb@gmail.com", &Time::new(seconds, 0)).unwrap();
+            repository
+                .commit(refname, &signature, &signature, "A commit", &tree, &parents)
+                .unwrap()
+        };
+
+        let base = commit(Some("HEAD"), &["a"], 1633305600, &[]);
+        let feature = commit(Some("refs/heads/feature"), &["a", "b"], 1633309200, &[base]);
+        // the feature cherry-picked onto the mainline
+        let picked = commit(Some("HEAD"), &["a", "b"], 1633312800, &[base]);
+        commit(Some("HEAD"), &["a", "b"], 1633316400, &[picked, feature]);
+        commit(
+            Some("refs/remotes/origin/experiment"),
+            &["a", "c"],
+            1633320000,
+            &[base],
+        );
+
+        let identities = [Identity::from_author("Tom Jones")];
+        let ids = |filter: &CommitFilter| -> Vec<Oid> {
+            collect_commits(&path, &identities, filter, false)
+                .unwrap()
+                .iter()
+                .map(|commit| Oid::from_str(&commit.id).unwrap())
+                .collect()
+        };
+
+        let commits = ids(&CommitFilter::default());
+        assert_eq!(commits.len(), 4);
+        assert!(commits.contains(&feature));
+        assert!(!commits.contains(&picked));
+
+        let branches_only = CommitFilter {
+            include_refs: vec!["refs/heads/*".to_string()],
+            exclude_refs: vec!["refs/heads/feature".to_string()],
+            skip_merges: true,
+            ..Default::default()
+        };
+        assert_eq!(ids(&branches_only), vec![feature, base]);
+        let misspelt = CommitFilter {
+            include_refs: vec!["refs/heads/*".to_string(), "refs/remote/*".to_string()],
+            ..Default::default()
+        };
+        assert!(collect_commits(&path, &identities, &misspelt, false).is_err());
+
+        let only_c = CommitFilter {
+            paths: vec!["c".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(ids(&only_c).len(), 1);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn it_only_collects_commits_touching_a_projects_paths() {
+        let path = std::env::temp_dir().join(utils::generate_random_path());
+        let repository = Repository::init(&path).unwrap();
+        let mut parent: Option<Oid> = None;
+        for (seconds, file) in [
+            (1633305600, "services/acme/api/main.rs"),
+            (1633309200, "services/other/main.rs"),
+            (1633312800, "README.md"),
+            (1633316400, "services/acme/README.md"),
+        ]
+        .iter()
+        {
+            let file_path = path.join(file);
+            std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+            std::fs::write(&file_path, seconds.to_string()).unwrap();
+            let mut index = repository.index().unwrap();
+            index.add_path(Path::new(file)).unwrap();
+            index.write().unwrap();
+            let tree = repository.find_tree(index.write_tree().unwrap()).unwrap();
+            let parents: Vec<git2::Commit> = parent
+                .iter()
+                .map(|oid| repository.find_commit(*oid).unwrap())
+                .collect();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            let signature =
+                Signature::new("Tom Jones", "sex_bomb@gmail.com", &Time::new(*seconds, 0)).unwrap();
+            parent = Some(
+                repository
+                    .commit(Some("HEAD"), &signature, &signature, file, &tree, &parents)
+                    .unwrap(),
+            );
+        }
+
+        let summaries = |paths: &[&str]| -> Vec<String> {
+            let filter = CommitFilter {
+                paths: paths.iter().map(|path| path.to_string()).collect(),
+                ..Default::default()
+            };
+            collect_commits(&path, &[Identity::from_author("Tom Jones")], &filter, false)
+                .unwrap()
+                .into_iter()
+                .map(|commit| commit.summary)
+                .collect()
+        };
+
+        assert_eq!(
+            summaries(&["services/acme/**"]),
+            vec!["services/acme/README.md", "services/acme/api/main.rs"]
+        );
+        assert_eq!(
+            summaries(&["services/other", "*.md"]),
+            vec![
+                "services/acme/README.md",
+                "README.md",
+                "services/other/main.rs"
+            ]
+        );
+        assert_eq!(summaries(&[]).len(), 4);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn it_keeps_the_names_of_merged_branches() {
+        let path =
+            create_repository_with_commits(&[("Tom Jones", "sex_bomb@gmail.com", 1633305600, 0)]);
+        let repository = Repository::open(&path).unwrap();
+        let base = repository.head().unwrap().peel_to_commit().unwrap();
+        let tree = base.tree().unwrap();
+        let commit = |refname: &str, summary: &str, seconds: i64, parents: &[&git2::Commit]| {
+            let signature =
+                Signature::new("Tom Jones", "sex_bomb@gmail.com", &Time::new(seconds, 0)).unwrap();
+            let oid = repository
+                .commit(
+                    Some(refname),
+                    &signature,
+                    &signature,
+                    summary,
+                    &tree,
+                    parents,
+                )
+                .unwrap();
+            repository.find_commit(oid).unwrap()
+        };
+
+        // merged into the mainline, then deleted
+        let login = commit(
+            "refs/heads/feature/ABC-12-login",
+            "Add login",
+            1633309200,
+            &[&base],
+        );
+        let merge = commit(
+            "HEAD",
+            "Merge pull request #7 from tom/feature/ABC-12-login",
+            1633312800,
+            &[&base, &login],
+        );
+        repository
+            .find_reference("refs/heads/feature/ABC-12-login")
+            .unwrap()
+            .delete()
+            .unwrap();
+        // still open, with a second name that isn't read from
+        let release = commit("refs/heads/release", "Fix the build", 1633316400, &[&merge]);
+        repository
+            .reference("refs/heads/wip/XYZ-9", release.id(), false, "")
+            .unwrap();
+
+        let identities = [Identity::from_author("Tom Jones")];
+        let branches = |filter: &CommitFilter| -> Vec<(String, Vec<String>)> {
+            collect_commits(&path, &identities, filter, true)
+                .unwrap()
+                .into_iter()
+                .map(|commit| (commit.summary, commit.branches))
+                .collect()
+        };
+
+        let everything = branches(&CommitFilter::default());
+        assert_eq!(everything[0].0, "Fix the build");
+        assert_eq!(everything[0].1, vec!["release", "wip/XYZ-9"]);
+        assert_eq!(
+            everything[2],
+            (
+                "Add login".to_string(),
+                vec!["feature/ABC-12-login".to_string()]
+            )
+        );
+
+        let filter = CommitFilter {
+            exclude_refs: vec!["refs/heads/wip/*".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(branches(&filter), {
+            let mut expected = everything.clone();
+            expected[0].1 = vec!["release".to_string()];
+            expected
+        });
+
+        // branched from release, so nothing is on an open branch
+        let filter = CommitFilter {
+            mainline: Some("refs/heads/release".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(branches(&filter)[0].1, Vec::<String>::new());
+        assert_eq!(branches(&filter)[2].1, vec!["feature/ABC-12-login"]);
+
+        let without_branches =
+            collect_commits(&path, &identities, &CommitFilter::default(), false).unwrap();
+        assert!(without_branches
+            .iter()
+            .all(|commit| commit.branches.is_empty()));
+        std::fs::remove_dir_all(&path).unwrap();
+
+        let merge_subject = Regex::new(MERGE_SUBJECT).unwrap();
+        assert_eq!(
+            merged_branch(&merge_subject, "Merge branch 'feature/ABC-1' into 'main'").as_deref(),
+            Some("feature/ABC-1")
+        );
+        assert_eq!(
+            merged_branch(
+                &merge_subject,
+                "Merge remote-tracking branch 'origin/ABC-2'"
+            )
+            .as_deref(),
+            Some("ABC-2")
+        );
+        assert_eq!(merged_branch(&merge_subject, "Add login"), None);
+    }
+
+    #[test]
+    fn it_summarises_a_days_commit_subjects() {
+        let commit = |minute: u32, summary: &str, is_merge: bool| Commit {
+            id: summary.to_string(),
+            author_name: "Tom Jones".to_string(),
+            author_email: "sex_bomb@gmail.com".to_string(),
+            author_time: DateTime::parse_from_rfc3339(&format!(
+                "2021-10-04T09:{:02}:00+01:00",
+                minute
+            ))
+            .unwrap(),
+            committer_time: DateTime::parse_from_rfc3339(&format!(
+                "2021-10-04T09:{:02}:00+01:00",
+                minute
+            ))
+            .unwrap(),
+            summary: summary.to_string(),
+            message: summary.to_string(),
+            is_merge,
+            branches: vec![],
+        };
+        // newest first, as they come out of the revwalk
+        let commits = vec![
+            commit(50, "Merge branch 'main' into feature", true),
+            commit(40, "fixup! Add invoice totals", false),
+            commit(30, "add invoice totals", false),
+            commit(20, "Fix the date picker", false),
+            commit(10, "Add invoice totals", false),
+        ];
+
+        let config = NotesConfig::default();
+        let time = TimeConfig::default();
+        assert_eq!(
+            summarise(&commits, &config, &time).as_deref(),
+            Some("Add invoice totals; Fix the date picker")
+        );
+
+        let config = NotesConfig {
+            max_length: Some(24),
+            ..config
+        };
+        assert_eq!(
+            summarise(&commits, &config, &time).as_deref(),
+            Some("Add invoice totals; Fix…")
+        );
+        assert_eq!(summarise(&commits[..2], &config, &time), None);
+
+        // rebased, so committed in the opposite order to when they were written
+        let mut rebased = vec![
+            commit(20, "Write the tests", false),
+            commit(10, "Fix the bug", false),
+        ];
+        rebased[0].committer_time = commits[4].committer_time;
+        rebased[1].committer_time = commits[3].committer_time;
+        let committed = TimeConfig {
+            source: TimeSource::Committer,
+            ..Default::default()
+        };
+        assert_eq!(
+            summarise(&rebased, &NotesConfig::default(), &time).as_deref(),
+            Some("Fix the bug; Write the tests")
+        );
+        assert_eq!(
+            summarise(&rebased, &NotesConfig::default(), &committed).as_deref(),
+            Some("Write the tests; Fix the bug")
+        );
+    }
+
+    #[test]
+    fn it_buckets_commits_by_author_or_committer_date() {
+        let commit = |id: &str, committed: &str| Commit {
+            id: id.to_string(),
+            author_name: "Tom Jones".to_string(),
+            author_email: "sex_bomb@gmail.com".to_string(),
+            author_time: DateTime::parse_from_rfc3339("2021-10-04T09:00:00+01:00").unwrap(),
+            committer_time: DateTime::parse_from_rfc3339(committed).unwrap(),
+            summary: "Add invoice totals".to_string(),
+            message: "Add invoice totals".to_string(),
+            is_merge: false,
+            branches: vec![],
+        };
+        let october = |day| NaiveDate::from_ymd_opt(2021, 10, day).unwrap();
+        // rebased two days after it was written, and amended the same evening
+        let rebased = commit("0123456789abcdef", "2021-10-06T12:00:00+01:00");
+        let amended = commit("fedcba9876543210", "2021-10-04T21:00:00+01:00");
+
+        let config = TimeConfig::default();
+        assert_eq!(config.day_of(&config.commit_time(&rebased)), october(4));
+        let config = TimeConfig {
+            source: TimeSource::Committer,
+            ..Default::default()
+        };
+        assert_eq!(config.day_of(&config.commit_time(&rebased)), october(6));
+        assert_eq!(
+            serde_json::from_str::<TimeConfig>(r#"{"source":"committer"}"#).unwrap(),
+            config
+        );
+
+        assert!(rebased.dates_differ());
+        assert!(!amended.dates_differ());
+        assert_eq!(rewritten_commits_warning(&[], TimeSource::Author), None);
+        let warning = rewritten_commits_warning(&[rebased], TimeSource::Committer).unwrap();
+        assert!(warning.contains("the committer date is used"));
+        assert!(warning.ends_with(
+            "0123456 authored Mon 4 Oct 2021, committed Wed 6 Oct 2021: Add invoice totals"
+        ));
+    }
+
+    #[test]
+    fn it_buckets_commit_times_into_days_in_the_reporting_timezone() {
+        // 00:30 on the 1st in Berlin, made by someone in New York
+        let time = DateTime::parse_from_rfc3339("2021-09-30T18:30:00-04:00").unwrap();
+        let september = |day| NaiveDate::from_ymd_opt(2021, 9, day).unwrap();
+
+        let config = TimeConfig::default();
+        assert_eq!(config.day_of(&time), september(30));
+
+        let mut config: TimeConfig =
+            serde_json::from_str(r#"{"timezone":"Europe/Berlin"}"#).unwrap();
+        assert_eq!(
+            config.day_of(&time),
+            NaiveDate::from_ymd_opt(2021, 10, 1).unwrap()
+        );
+
+        config.day_rollover_hour = 4;
+        assert_eq!(config.day_of(&time), september(30));
+
+        config.day_rollover_hour = 24;
+        assert!(config.validate().is_err());
+        assert!(serde_json::from_str::<TimeConfig>(r#"{"timezone":"Mars/Olympus"}"#).is_err());
+    }
+}
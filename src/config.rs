@@ -1,34 +1,77 @@
-extern crate bson;
-
-use mongodb::bson::doc;
 use std::error::Error;
-use std::fs::File;
-use std::io::{ErrorKind, Read};
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
-use std::{env, io, process};
-use tokio;
-
+use std::{io, process};
+
+use crate::calendar::DayKind;
+use crate::cli::{Cli, Command, PeriodArgs};
+use crate::export::{self, Format};
+use crate::invoice::Ledger;
+use crate::period::Period;
+use crate::projects::Projects;
 use crate::repo;
-use crate::{db, utils};
+use crate::store::{self, TimesheetDocument, EXPIRE_TIME_SECONDS};
+use crate::{commits, tickets, utils};
 
-use chrono::{self, Datelike, Utc};
+use crate::editor::Editor;
+use crate::timesheet::{DayEntry, DayOverride, Hours, TimeEntry, Timesheet};
+use chrono::{self, DateTime, FixedOffset, NaiveDate, Utc};
 use git2::Repository;
-use serde_json::{json, Map, Value};
-use std::process::Command;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 const CONFIG_FILE_NAME: &str = ".timesheet-gen.txt";
+const INVOICES_FILE_NAME: &str = ".timesheet-gen-invoices.json";
 
-#[derive(Debug)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum Commands {
     Init,
     Make,
+    Show,
+    Edit,
+    Log,
+    Invoice,
+    Revoke,
+}
+
+// What the invoice command does
+#[derive(PartialEq, Debug, Clone, Default)]
+pub enum InvoiceAction {
+    #[default]
+    Issue,
+    List,
+    // Render an issued invoice again, by its reference
+    Render(String),
+}
+
+// The commits made on a day, across every repository in a project
+#[derive(Default)]
+struct CommitDay {
+    commits: Vec<commits::Commit>,
+    repositories: BTreeSet<String>,
 }
 
 #[derive(PartialEq, Debug)]
 pub struct Config {
-    pub command: String,
+    pub command: Commands,
     pub repository_path: Option<String>,
     pub home_path: PathBuf,
+    pub period: Period,
+    pub add_repository: bool,
+    pub project: Option<String>,
+    pub set_default: bool,
+    pub client: repo::ClientOptions,
+    pub random_path: Option<String>,
+    pub format: Format,
+    pub output: Option<PathBuf>,
+    pub edit_date: Option<NaiveDate>,
+    // None when the edits to the day are being discarded
+    pub day_override: Option<DayOverride>,
+    // Edit the days in the full screen calendar
+    pub interactive: bool,
+    pub invoice_action: InvoiceAction,
+    // Time logged by hand, one entry per day
+    pub time_entries: Vec<TimeEntry>,
 }
 
 pub trait Onboarding {
@@ -43,81 +86,277 @@ pub trait Make {
     fn make(&self) -> Result<(), Box<dyn Error>>;
 }
 
+pub trait Show {
+    fn show(&self) -> Result<(), Box<dyn Error>>;
+}
+
+pub trait Edit {
+    fn edit(&self) -> Result<(), Box<dyn Error>>;
+}
+
+pub trait Log {
+    fn log(&self) -> Result<(), Box<dyn Error>>;
+}
+
+pub trait Invoice {
+    fn invoice(&self) -> Result<(), Box<dyn Error>>;
+}
+
+pub trait Revoke {
+    fn revoke(&self) -> Result<(), Box<dyn Error>>;
+}
+
 pub trait GetCommand {
     fn get_command(&self) -> Commands;
 }
 
 impl GetCommand for Config {
     fn get_command(&self) -> Commands {
-        self.command.parse().unwrap()
+        self.command
     }
 }
 
 impl Make for Config {
     #[tokio::main]
     async fn make(&self) -> Result<(), Box<dyn Error>> {
-        let expire_time_seconds = 1800;
-        println!("Generating timesheet for {}...", self.parse_month_string());
-
-        let user_data: repo::Repo = self.find_user_data()?;
-
-        let db = db::Db::new().await?;
-        let collection = db
-            .client
-            .database("timesheet-gen")
-            .collection("timesheet-temp-paths");
-
-        let random_path = db.generate_random_path(&collection).await?;
-
-        // example of how to convert to json
-        // let timesheet = json!(user_data.timesheet).to_string();
-        // let json: Map<String, Value> = serde_json::from_str(&timesheet)?;
-        // println!("{:#?}", json);
-
-        let timesheet = doc! {
-            "creation_date": Utc::now(),
-            "random_path": &random_path,
-            "name" : user_data.name,
-            "email" : user_data.email,
-            "namespace" : user_data.namespace,
-            "path" : user_data.path,
-            "client_name" : user_data.client_name,
-            "client_contact_person" : user_data.contact_person,
-            "address" : user_data.address,
-            "timesheet" : json!(user_data.timesheet).to_string(),
-        };
+        let mut projects = self.read_config_file()?;
+        if self.interactive {
+            self.review_timesheet(&mut projects)?;
+        }
+        let user_data: repo::Repo = self.find_user_data(&projects)?;
+        Config::warn_of_flagged_days(&user_data);
+
+        if let Some(rendered) = export::render(self.format, &user_data, &self.period) {
+            let output = self
+                .output
+                .clone()
+                .or_else(|| export::default_output(self.format, &user_data, &self.period));
+            export::write(&rendered, output.as_deref())?;
+            if let Some(output) = &output {
+                println!(
+                    "Timesheet for {} written to {}",
+                    self.period,
+                    output.display()
+                );
+            }
+            process::exit(exitcode::OK);
+        }
+
+        println!("Generating timesheet for {}...", self.period);
+
+        if user_data.timesheet.is_empty() {
+            println!("No commits found for {}", user_data.name);
+        } else {
+            println!("{} hours logged", user_data.timesheet.total_hours());
+        }
+
+        let store = projects.storage.store().await?;
+        store.expire().await?;
+
+        let random_path = store::generate_random_path(&*store).await?;
+        let timesheet = TimesheetDocument::new(&user_data, random_path);
+        let location = store.publish(&timesheet).await?;
+
+        println!(
+            "Timesheet now available for {} minutes @ {}",
+            EXPIRE_TIME_SECONDS / 60,
+            location
+        );
+
+        process::exit(exitcode::OK);
+    }
+}
+
+impl Show for Config {
+    fn show(&self) -> Result<(), Box<dyn Error>> {
+        let projects = self.read_config_file()?;
+        let user_data: repo::Repo = self.find_user_data(&projects)?;
+        Config::warn_of_flagged_days(&user_data);
+
+        if let Some(rendered) = export::render(self.format, &user_data, &self.period) {
+            export::write(&rendered, None)?;
+        }
+
+        process::exit(exitcode::OK);
+    }
+}
+
+impl Edit for Config {
+    fn edit(&self) -> Result<(), Box<dyn Error>> {
+        let config_path = self.get_filepath();
+        let mut projects = self.read_config_file()?;
+
+        if self.interactive {
+            match self.review_timesheet(&mut projects)? {
+                true => println!("Changes to {} saved", self.period),
+                false => println!("No changes saved"),
+            }
+            process::exit(exitcode::OK);
+        }
 
-        // Check for existing index for TTL on the collection
-        let index_names = collection.list_index_names().await?;
-
-        if !index_names.contains(&String::from("expiration_date")) {
-            // create TTL index to expire documents after 30 minutes
-            db.client
-                .database("timesheet-gen")
-                .run_command(
-                    doc! {
-                        "createIndexes": "timesheet-temp-paths",
-                        "indexes": [
-                            {
-                                "key": { "creation_date": 1 },
-                                "name": "expiration_date",
-                                "expireAfterSeconds": expire_time_seconds,
-                                "unique": true
-                            },
-                        ]
-                    },
-                    None,
-                )
-                .await?;
+        let date = self.edit_date.ok_or("No date given to edit")?;
+        if let Some(edit) = &self.day_override {
+            let project = projects.find(self.project.as_deref())?;
+            let mut merged = project.overrides.get(&date).cloned().unwrap_or_default();
+            merged.merge(edit.clone());
+            Config::build_timesheet(project, &Period::new(date, date)?)?
+                .check_override(date, &merged)?;
         }
 
-        collection.insert_one(timesheet.clone(), None).await?;
+        let project = projects.find_mut(self.project.as_deref())?;
+        match &self.day_override {
+            Some(edit) => {
+                project
+                    .overrides
+                    .entry(date)
+                    .or_default()
+                    .merge(edit.clone());
+                println!(
+                    "{} updated for {}",
+                    date.format("%A %-d %B %Y"),
+                    project.namespace
+                );
+            }
+            None => {
+                project.overrides.remove(&date);
+                println!(
+                    "{} reset to the git log for {}",
+                    date.format("%A %-d %B %Y"),
+                    project.namespace
+                );
+            }
+        }
+
+        projects.save(&config_path)?;
+        process::exit(exitcode::OK);
+    }
+}
+
+impl Log for Config {
+    fn log(&self) -> Result<(), Box<dyn Error>> {
+        let config_path = self.get_filepath();
+        let mut projects = self.read_config_file()?;
+        let project = projects.find_mut(self.project.as_deref())?;
+
+        let mut logged = 0;
+        for entry in self.time_entries.iter() {
+            // leave is only taken on working days, and only once a day
+            if entry.kind.is_leave()
+                && (!project.calendar.is_working_day(entry.date)?
+                    || project
+                        .entries
+                        .iter()
+                        .any(|existing| existing.date == entry.date && existing.kind.is_leave()))
+            {
+                continue;
+            }
+            project.entries.push(entry.clone());
+            logged += 1;
+        }
+        project.entries.sort_by_key(|entry| entry.date);
+
+        match self.time_entries.first() {
+            Some(entry) if entry.kind.is_leave() => println!(
+                "{} working days of {} taken for {}",
+                logged,
+                entry.kind.label().to_lowercase(),
+                project.namespace
+            ),
+            Some(entry) => println!(
+                "{} hours of {} logged on {} for {}",
+                entry.hours,
+                entry.kind.label().to_lowercase(),
+                entry.date.format("%A %-d %B %Y"),
+                project.namespace
+            ),
+            None => {}
+        }
+
+        projects.save(&config_path)?;
+        process::exit(exitcode::OK);
+    }
+}
+
+impl Invoice for Config {
+    fn invoice(&self) -> Result<(), Box<dyn Error>> {
+        let ledger_path = self.home_path.join(INVOICES_FILE_NAME);
+        let mut ledger = Ledger::read(&ledger_path)?;
+
+        let issued = match &self.invoice_action {
+            InvoiceAction::List => {
+                if ledger.invoices.is_empty() {
+                    println!("No invoices have been issued yet");
+                }
+                for invoice in ledger.invoices.iter() {
+                    println!(
+                        "{}  {}  {}  {}  {} {}",
+                        invoice.reference,
+                        invoice.issue_date,
+                        invoice.project,
+                        invoice.period(),
+                        invoice.totals.currency,
+                        invoice.totals.total
+                    );
+                }
+                process::exit(exitcode::OK);
+            }
+            InvoiceAction::Render(reference) => ledger
+                .find(reference)
+                .ok_or(format!("No invoice has been issued as {}", reference))?
+                .clone(),
+            InvoiceAction::Issue => {
+                let projects = self.read_config_file()?;
+                let user_data: repo::Repo = self.find_user_data(&projects)?;
+                let invoice = ledger
+                    .issue(
+                        &user_data,
+                        &self.period,
+                        &projects.invoicing,
+                        Utc::now().date_naive(),
+                    )?
+                    .clone();
+                // the number is only used once the invoice is saved, so the sequence
+                // stays gap free if writing the files below fails
+                ledger.write(&ledger_path)?;
+                invoice
+            }
+        };
+
+        let directory = self.output.clone().unwrap_or_else(|| PathBuf::from("."));
+        let pdf = directory.join(format!("{}.pdf", issued.reference));
+        let json = directory.join(format!("{}.json", issued.reference));
+        export::write(&export::render_invoice(&issued), Some(&pdf))?;
+        export::write(
+            serde_json::to_string_pretty(&issued)?.as_bytes(),
+            Some(&json),
+        )?;
 
         println!(
-            "Timesheet now available for {} minutes @ https://timesheet-gen.io/{}",
-            expire_time_seconds / 60,
-            &random_path
+            "Invoice {} for {} {} written to {} and {}",
+            issued.reference,
+            issued.totals.currency,
+            issued.totals.total,
+            pdf.display(),
+            json.display()
         );
+        process::exit(exitcode::OK);
+    }
+}
+
+impl Revoke for Config {
+    #[tokio::main]
+    async fn revoke(&self) -> Result<(), Box<dyn Error>> {
+        let random_path = self.random_path.as_deref().unwrap_or_default();
+        let store = self.read_config_file()?.storage.store().await?;
+
+        if store.revoke(random_path).await? {
+            println!("Timesheet {} has been removed", random_path);
+        } else {
+            println!(
+                "Timesheet {} was not found, it may have expired",
+                random_path
+            );
+        }
 
         process::exit(exitcode::OK);
     }
@@ -125,10 +364,24 @@ impl Make for Config {
 
 impl Initialise for Config {
     fn initialise(&self) -> Result<(), Box<dyn Error>> {
-        let repo = self.find_user_data()?;
+        if self.add_repository {
+            return self.add_repository_to_config();
+        }
+
+        // a project that isn't in the config yet is a new client to onboard
+        let projects = self.read_config_file()?;
+        if self.project.is_some() && projects.find(self.project.as_deref()).is_err() {
+            self.onboarding();
+        }
+
+        if self.set_default {
+            return self.set_default_project(projects);
+        }
+
+        let repo = self.find_user_data(&projects)?;
         // show the user the contents of the config file
         // and prompt as to whether this file should be used
-        self.prompt_for_config_use(repo);
+        self.prompt_for_config_use(&projects, repo);
         Ok(())
     }
 }
@@ -140,6 +393,7 @@ impl Onboarding for Config {
 
         match &self.repository_path {
             Some(arg) => path = String::from(arg),
+            None if self.client.assume_yes => path = String::from("."),
             None => {
                 println!("Initialise timesheet-gen for current repository? (Y/n)");
                 path = String::from(&self.use_current_repository());
@@ -161,58 +415,188 @@ impl Onboarding for Config {
 
 // Creates a struct with commands and path data
 impl Config {
-    pub fn new(mut args: env::Args) -> Result<Config, io::Error> {
-        let argument_length = args.len();
-
-        if argument_length <= 1 {
-            let not_enough_arguments =
-                io::Error::new(ErrorKind::InvalidInput, format!("Not enough arguments"));
-            return Err(not_enough_arguments);
-        }
-
-        args.next();
-        let command = match args.next() {
-            Some(arg) => arg,
-            None => {
-                return Err(io::Error::new(
-                    ErrorKind::InvalidInput,
-                    format!("Didn't get a query 'command'"),
-                ))
-            }
-        };
-
-        let repository_path = args.next();
-
+    pub fn new(cli: Cli) -> Result<Config, io::Error> {
         let home_path = match dirs::home_dir() {
             Some(dir) => dir,
             None => panic!("Home directory not found"),
         };
 
-        Ok(Config {
-            command,
-            repository_path,
+        let mut config = Config {
+            command: Commands::Make,
+            repository_path: None,
             home_path,
-        })
+            period: Period::current_month(Utc::now().date_naive()),
+            add_repository: false,
+            project: None,
+            set_default: false,
+            client: repo::ClientOptions::default(),
+            random_path: None,
+            format: Format::default(),
+            output: None,
+            edit_date: None,
+            day_override: None,
+            interactive: false,
+            invoice_action: InvoiceAction::default(),
+            time_entries: vec![],
+        };
+
+        match cli.command {
+            Command::Init(args) => {
+                config.command = Commands::Init;
+                config.repository_path = args.path;
+                config.add_repository = args.add;
+                config.project = args.project.project;
+                config.set_default = args.default;
+                config.client = repo::ClientOptions {
+                    client_name: args.client.client_name,
+                    contact_person: args.client.contact,
+                    address: args.client.address,
+                    assume_yes: args.client.yes,
+                };
+            }
+            Command::Make(args) => {
+                config.command = Commands::Make;
+                config.project = args.project.project;
+                config.period = Config::parse_period(args.period)
+                    .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+                config.format = args.format;
+                config.output = args.output;
+                config.interactive = args.review;
+            }
+            Command::Show(args) => {
+                config.command = Commands::Show;
+                config.project = args.project.project;
+                config.period = Config::parse_period(args.period)
+                    .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+                config.format = match args.format {
+                    Format::Text | Format::Markdown => args.format,
+                    _ => {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidInput,
+                            "show only prints text or markdown, try 'make --format'",
+                        ))
+                    }
+                };
+            }
+            Command::Edit(args) => {
+                config.command = Commands::Edit;
+                config.project = args.project.project;
+                config.interactive = args.interactive;
+                if let Some(month) = args.month {
+                    config.period = Period::parse_month(&month)
+                        .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+                }
+                config.edit_date = args
+                    .date
+                    .map(|date| Period::parse_date(&date))
+                    .transpose()
+                    .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+                if let Some(hours) = args.hours.filter(|hours| !(0.0..=24.0).contains(hours)) {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("{} isn't a number of hours in a day", hours),
+                    ));
+                }
+                config.day_override = match args.reset {
+                    true => None,
+                    false => Some(DayOverride {
+                        hours: args.hours.map(Hours::from_hours),
+                        note: args.note,
+                        added: args.add,
+                        removed: args.remove,
+                    }),
+                };
+            }
+            Command::Log(args) => {
+                config.command = Commands::Log;
+                config.project = args.project.project;
+                if args.kind.is_leave() {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        "leave is taken with 'timesheet-gen leave'",
+                    ));
+                }
+                if !(0.0..=24.0).contains(&args.hours) {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("{} isn't a number of hours in a day", args.hours),
+                    ));
+                }
+                config.time_entries = vec![TimeEntry {
+                    date: Period::parse_date(&args.date)
+                        .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?,
+                    kind: args.kind,
+                    hours: Hours::from_hours(args.hours),
+                    note: args.note,
+                }];
+            }
+            Command::Leave(args) => {
+                config.command = Commands::Log;
+                config.project = args.project.project;
+                if !args.kind.is_leave() {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        "time that isn't leave is logged with 'timesheet-gen log'",
+                    ));
+                }
+                let from = Period::parse_date(&args.from)
+                    .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+                let to = match args.to {
+                    Some(to) => Period::parse_date(&to)
+                        .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?,
+                    None => from,
+                };
+                let period = Period::new(from, to)
+                    .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+                let (kind, note) = (args.kind, args.note);
+                config.time_entries = period
+                    .days()
+                    .map(|date| TimeEntry {
+                        date,
+                        kind,
+                        hours: Hours::default(),
+                        note: note.clone(),
+                    })
+                    .collect();
+            }
+            Command::Invoice(args) => {
+                config.command = Commands::Invoice;
+                config.project = args.project.project;
+                config.period = Config::parse_period(args.period)
+                    .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+                config.output = args.output;
+                config.invoice_action = match (args.list, args.render) {
+                    (true, _) => InvoiceAction::List,
+                    (false, Some(reference)) => InvoiceAction::Render(reference),
+                    (false, None) => InvoiceAction::Issue,
+                };
+            }
+            Command::Revoke(args) => {
+                config.command = Commands::Revoke;
+                config.project = args.project.project;
+                config.random_path = Some(args.path);
+            }
+        };
+
+        Ok(config)
     }
 
-    fn parse_month_string(&self) -> String {
-        let months: Vec<&str> = vec![
-            "January",
-            "February",
-            "March",
-            "April",
-            "May",
-            "June",
-            "July",
-            "August",
-            "September",
-            "October",
-            "November",
-            "December",
-        ];
-
-        let month_num = Utc::now().month() as usize;
-        months[month_num - 1].replace("\"", "")
+    // Resolve the period flags, defaulting to the current month
+    fn parse_period(period: PeriodArgs) -> Result<Period, String> {
+        let today = Utc::now().date_naive();
+
+        match period {
+            PeriodArgs {
+                month: Some(month), ..
+            } => Period::parse_month(&month),
+            PeriodArgs {
+                from: Some(from),
+                to: Some(to),
+                ..
+            } => Period::new(Period::parse_date(&from)?, Period::parse_date(&to)?),
+            PeriodArgs { previous: true, .. } => Ok(Period::previous_month(today)),
+            _ => Ok(Period::current_month(today)),
+        }
     }
 
     fn get_filepath(&self) -> String {
@@ -220,100 +604,231 @@ impl Config {
         home_string.unwrap().to_owned() + "/" + CONFIG_FILE_NAME
     }
 
-    fn find_user_data(&self) -> Result<repo::Repo, Box<dyn Error>> {
+    fn read_config_file(&self) -> Result<Projects, Box<dyn Error>> {
         let config_path = self.get_filepath();
-        let mut buffer = String::new();
 
-        match File::open(&config_path) {
-            Ok(mut file) => {
-                file.read_to_string(&mut buffer)?;
-            }
-            Err(_) => {
-                println!("This looks like the first time you're running timesheet-gen");
-                self.onboarding();
-            }
-        };
+        if !Path::new(&config_path).exists() {
+            println!("This looks like the first time you're running timesheet-gen");
+            self.onboarding();
+        }
+
+        Projects::read_config_file(&config_path)
+    }
 
-        let config_details: repo::Repo = serde_json::from_str(&*buffer)?;
-        let repository = Repository::open(config_details.path)?;
-        let path = repository.path();
-        let timesheet = self.build_months_from_git_log(&config_details.name, path)?;
-        let repo = repo::Repo::new(
-            Some(config_details.namespace),
+    fn find_user_data(&self, projects: &Projects) -> Result<repo::Repo, Box<dyn Error>> {
+        let config_details = projects.find(self.project.as_deref())?.clone();
+        let repository = Repository::open(&config_details.path)?;
+        let path = repository.path().display().to_string();
+        let mut timesheet = Config::build_timesheet(&config_details, &self.period)?;
+
+        for (date, edit) in config_details
+            .overrides
+            .range(self.period.from..=self.period.to)
+        {
+            timesheet.apply_override(*date, edit);
+        }
+
+        Ok(repo::Repo {
             path,
-            config_details.name,
-            config_details.email,
-            config_details.client_name,
-            config_details.contact_person,
-            config_details.address,
             timesheet,
-        )?;
+            ..config_details
+        })
+    }
 
-        Ok(repo)
+    // Days with commits outside of the working week that the project's policy
+    // asks to be reviewed, on stderr so that they aren't part of the output
+    fn warn_of_flagged_days(repo: &repo::Repo) {
+        for sheet in repo.timesheet.months.iter() {
+            for (date, day) in sheet.days.iter() {
+                if let Some(day_off) = day.day_off.as_ref().filter(|day_off| day_off.flagged) {
+                    eprintln!(
+                        "Warning: {} ({}) has commits on a day off, review it with 'timesheet-gen edit'",
+                        date.format("%a %-d %b %Y"),
+                        day_off.reason
+                    );
+                }
+            }
+        }
     }
 
-    // TODO not performant to have regex here. Consider memoization through lazy static
-    fn build_months_from_git_log(
-        &self,
-        name: &String,
-        path: &Path,
-    ) -> Result<Map<String, Value>, Box<dyn Error>> {
-        let command = String::from("--author");
-        let author = [command, name.to_owned()].join("=");
-        let output = Command::new("git")
-            .arg("-C")
-            .arg(path)
-            .arg("log")
-            .arg(author)
-            .arg("--all")
-            .output()
-            .expect("Failed to execute command");
-
-        let output_string = String::from_utf8(output.stdout)?;
-
-        // group commit dates by year and by month
-        let year_regex = String::from(r"(\s(?P<year>(20|19){1}[0-9]{2})\s)");
-        let year_matches = utils::find_named_matches(year_regex, &output_string);
-
-        let mut year_map = Map::new();
-
-        // create json to represent the years/months/days for each for each of the dates in the commit
-        for year in year_matches.iter() {
-            let mut month_map = Map::new();
-            let month_regex = format!(
-                r"([a-zA-Z]{{3}})\s(?P<month>[a-zA-Z]{{3}})\s(\d{{1,2}})\s(\d+:?){{3}}\s{}",
-                year
-            );
+    // Open the full screen calendar on the period, saving the edits to the
+    // config file. Returns false if the editor was quit without saving
+    fn review_timesheet(&self, projects: &mut Projects) -> Result<bool, Box<dyn Error>> {
+        let project = projects.find(self.project.as_deref())?;
+        let editor = Editor::new(
+            format!("Timesheet for {}", project.namespace),
+            self.period,
+            Config::build_timesheet(project, &self.period)?,
+            project.overrides.clone(),
+            Hours::from_hours(project.hours.daily_cap_hours),
+        );
 
-            let month_matches = utils::find_named_matches(month_regex, &output_string);
+        match editor.run()? {
+            Some(overrides) => {
+                projects.find_mut(self.project.as_deref())?.overrides = overrides;
+                projects.save(&self.get_filepath())?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
 
-            for month in month_matches.iter() {
-                let mut day_map = Map::new();
-                let day_regex = format!(
-                    r"([a-zA-Z]{{3}})\s{}\s(?P<day>\d{{1,2}})\s(\d+:?){{3}}\s{}",
-                    month, year
-                );
+    // The days found in the git log along with the time logged by hand,
+    // before any edits are applied
+    fn build_timesheet(project: &repo::Repo, period: &Period) -> Result<Timesheet, Box<dyn Error>> {
+        let mut timesheet = Config::build_months_from_git_log(project, period)?;
+        for entry in project
+            .entries
+            .iter()
+            .filter(|entry| period.contains(&entry.date))
+        {
+            timesheet.add_entry(entry);
+        }
 
-                let day_matches = utils::find_named_matches(day_regex, &output_string);
+        Ok(timesheet)
+    }
+
+    // The days found in the git log, before any edits are applied
+    fn build_months_from_git_log(
+        project: &repo::Repo,
+        period: &Period,
+    ) -> Result<Timesheet, Box<dyn Error>> {
+        // group the commits by day, in the reporting timezone, along with the
+        // repositories they came from. The same commit can be in more than one
+        // repository e.g. a fork, so it is only counted once
+        project.time.validate()?;
+        let identities = project.author_identities();
+        let issue_pattern = match &project.issue_pattern {
+            Some(pattern) => Some(Regex::new(pattern)?),
+            None => None,
+        };
+        let commit_filters = project.resolve_commit_filters()?;
+        let mut seen_commits = HashSet::new();
+        let mut commit_days: BTreeMap<NaiveDate, CommitDay> = BTreeMap::new();
+        let mut rewritten_commits = vec![];
+
+        for path in project.repository_paths() {
+            let repository = Repository::open(path)?;
+            let namespace = repo::namespace_from_path(repository.path())?;
+            let commits = commits::collect_commits(
+                repository.path(),
+                &identities,
+                &commit_filters.get(path).cloned().unwrap_or_default(),
+                issue_pattern.is_some(),
+            )?;
+
+            for commit in commits.into_iter() {
+                let date = project.time.day_of(&project.time.commit_time(&commit));
+                if commit.dates_differ()
+                    && (period.contains(&project.time.day_of(&commit.author_time))
+                        || period.contains(&project.time.day_of(&commit.committer_time)))
+                    && !rewritten_commits
+                        .iter()
+                        .any(|other: &commits::Commit| other.id == commit.id)
+                {
+                    rewritten_commits.push(commit.clone());
+                }
+                if !period.contains(&date) {
+                    continue;
+                }
 
-                for day in day_matches.iter() {
-                    let object = json!({
-                        "hours" : 8,
-                    });
+                let day = commit_days.entry(date).or_default();
+                day.repositories.insert(namespace.clone());
+                if seen_commits.insert(commit.id.clone()) {
+                    day.commits.push(commit);
+                }
+            }
+        }
+
+        if let Some(warning) =
+            commits::rewritten_commits_warning(&rewritten_commits, project.time.source)
+        {
+            eprintln!("{}", warning);
+        }
 
-                    day_map.insert(day.to_string(), Value::from(object));
+        let mut timesheet = Timesheet::new();
+        for (date, day) in commit_days {
+            let day_off = match project.calendar.classify(date)? {
+                DayKind::Working => None,
+                DayKind::Off(day_off) => Some(day_off),
+                DayKind::Excluded => continue,
+            };
+            let times: Vec<DateTime<FixedOffset>> = day
+                .commits
+                .iter()
+                .map(|commit| project.time.commit_time(commit))
+                .collect();
+            let hours = project.hours.estimate(&times);
+            let tickets = match &issue_pattern {
+                Some(pattern) => {
+                    let commit_keys: Vec<BTreeSet<String>> = day
+                        .commits
+                        .iter()
+                        .map(|commit| tickets::ticket_keys(pattern, commit))
+                        .collect();
+                    tickets::apportion(hours, &commit_keys)
                 }
+                None => BTreeMap::new(),
+            };
+            let entry = DayEntry {
+                hours,
+                note: commits::summarise(&day.commits, &project.notes, &project.time),
+                repositories: day.repositories,
+                tickets,
+                day_off,
+                ..Default::default()
+            };
+            timesheet.insert_day(date, entry);
+        }
+
+        Ok(timesheet)
+    }
 
-                month_map.insert(month.to_string(), Value::from(day_map));
+    fn add_repository_to_config(&self) -> Result<(), Box<dyn Error>> {
+        let mut projects = self.read_config_file()?;
+        let config_details = projects.find_mut(self.project.as_deref())?;
+
+        let path = match &self.repository_path {
+            Some(arg) => String::from(arg),
+            None if self.client.assume_yes => String::from("."),
+            None => {
+                println!(
+                    "Add the current repository to '{}'? (Y/n)",
+                    config_details.namespace
+                );
+                self.use_current_repository()
             }
+        };
 
-            year_map.insert(year.to_string(), Value::Object(month_map));
+        let repository = Repository::open(&path)?;
+        if !config_details.add_repository(repository.path()) {
+            println!(
+                "{} is already part of '{}'",
+                repository.path().display(),
+                config_details.namespace
+            );
+            process::exit(exitcode::OK);
         }
 
-        Ok(year_map)
+        println!(
+            "Added {} to '{}'",
+            repository.path().display(),
+            config_details.namespace
+        );
+        projects.write_config_file(&self.get_filepath())
+    }
+
+    fn set_default_project(&self, mut projects: Projects) -> Result<(), Box<dyn Error>> {
+        let name = match &self.project {
+            Some(name) => name,
+            None => return Err("'--default' requires a '--project'".into()),
+        };
+
+        projects.set_default_project(name)?;
+        println!("'{}' is now the default project", name);
+        projects.write_config_file(&self.get_filepath())
     }
 
-    // TODO allow the user to edit these values
     fn create_user_config(&self, path: &str, config_path: &String) -> Result<(), Box<dyn Error>> {
         let mut repo: repo::Repo =
             crate::utils::find_repository_details(&*path).unwrap_or_else(|err| {
@@ -321,8 +836,25 @@ impl Config {
                 process::exit(1);
             });
 
-        repo.prompt_for_client_details()
-            .write_config_file(&config_path)
+        if let Some(project) = &self.project {
+            repo.namespace = project.to_owned();
+        }
+
+        // keep the projects that are already configured for other clients
+        let mut projects = match Path::new(config_path).exists() {
+            true => Projects::read_config_file(config_path)?,
+            false => Projects::default(),
+        };
+
+        repo.prompt_for_client_details(&self.client);
+        let namespace = repo.namespace.clone();
+        projects.upsert(repo);
+        if self.set_default {
+            projects.set_default_project(&namespace)?;
+        }
+
+        projects
+            .write_config_file(config_path)
             .unwrap_or_else(|err| {
                 eprintln!("Couldn't write to configuration file: {}", err);
                 process::exit(1);
@@ -331,16 +863,19 @@ impl Config {
         Ok(())
     }
 
-    fn prompt_for_config_use(&self, repo: repo::Repo) {
+    fn prompt_for_config_use(&self, projects: &Projects, repo: repo::Repo) {
         let config_path = self.get_filepath();
 
         println!(
             "timesheet-gen has found an existing configuration at:\n{}\n\
             \n\
+            Projects: {}\n\
+            \n\
             Name: {}\n\
             Email: {}\n\
             Project: {}\n\
             Git path: {}\n\
+            Additional repositories: {}\n\
             Client: {}\n\
             Client Contact person: {}\n\
             Client Address: \n\
@@ -348,16 +883,21 @@ impl Config {
             \n\
             Would you like to use this configuration? (Y/n)",
             config_path,
+            projects.names().join(", "),
             repo.name,
             repo.email,
             repo.namespace,
             repo.path,
+            repo.repositories.join(", "),
             repo.client_name,
             repo.contact_person,
             repo.address
         );
 
-        let option = utils::read_input().to_lowercase();
+        let option = match self.client.assume_yes {
+            true => String::from("y"),
+            false => utils::read_input().to_lowercase(),
+        };
         self.use_existing_configuration(Some(&option));
         process::exit(1);
     }
@@ -389,3 +929,91 @@ impl Config {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commits::Identity;
+    use chrono::TimeZone;
+    use git2::{Signature, Time};
+
+    // Each commit is on the empty tree, so the same list of commits gives the
+    // same ids in every repository it's made in
+    fn create_repository_with_commits(commits: &[(i64, &str)]) -> PathBuf {
+        let path = std::env::temp_dir().join(utils::generate_random_path());
+        let repository = Repository::init(&path).unwrap();
+        let tree_id = repository.index().unwrap().write_tree().unwrap();
+        let tree = repository.find_tree(tree_id).unwrap();
+
+        let mut parent: Option<git2::Oid> = None;
+        for (seconds, summary) in commits {
+            let signature =
+                Signature::new("Tom Jones", "sex_bomb@gmail.com", &Time::new(*seconds, 0)).unwrap();
+            let parents: Vec<git2::Commit> = parent
+                .iter()
+                .map(|oid| repository.find_commit(*oid).unwrap())
+                .collect();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            parent = Some(
+                repository
+                    .commit(
+                        Some("HEAD"),
+                        &signature,
+                        &signature,
+                        summary,
+                        &tree,
+                        &parents,
+                    )
+                    .unwrap(),
+            );
+        }
+
+        path
+    }
+
+    #[test]
+    fn it_counts_a_commit_in_several_repositories_once() {
+        // 09:00 and 11:00 on Monday 4 October 2021, the first commit in both
+        let shared = (1633338000, "ABC-1 Add login");
+        let original = create_repository_with_commits(&[shared]);
+        let fork = create_repository_with_commits(&[shared, (1633345200, "ABC-2 Add logout")]);
+        let git_path =
+            |path: &PathBuf| Repository::open(path).unwrap().path().display().to_string();
+        let project = repo::Repo {
+            namespace: "acme".to_string(),
+            path: git_path(&original),
+            repositories: vec![git_path(&fork)],
+            identities: vec![Identity::from_author("Tom Jones")],
+            issue_pattern: Some("[A-Z]+-\\d+".to_string()),
+            ..Default::default()
+        };
+
+        let period = Period::parse_month("2021-10").unwrap();
+        let timesheet = Config::build_months_from_git_log(&project, &period).unwrap();
+        std::fs::remove_dir_all(&original).unwrap();
+        std::fs::remove_dir_all(&fork).unwrap();
+
+        let days = &timesheet.months[0].days;
+        assert_eq!(days.len(), 1);
+        let monday = &days[&NaiveDate::from_ymd_opt(2021, 10, 4).unwrap()];
+        let times: Vec<DateTime<FixedOffset>> = [shared.0, 1633345200]
+            .iter()
+            .map(|seconds| {
+                FixedOffset::east_opt(0)
+                    .unwrap()
+                    .timestamp_opt(*seconds, 0)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(monday.hours, project.hours.estimate(&times));
+        // split evenly, as the shared commit is only counted once
+        assert_eq!(monday.tickets["ABC-1"], monday.tickets["ABC-2"]);
+        assert_eq!(
+            monday.repositories,
+            [&original, &fork]
+                .iter()
+                .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+                .collect()
+        );
+    }
+}
@@ -1,11 +1,12 @@
 extern crate dotenv;
 
+use crate::store::{TimesheetDocument, TimesheetStore, EXPIRE_TIME_SECONDS};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
 use dotenv::dotenv;
-use futures::TryStreamExt;
+use mongodb::bson::{self, doc, Bson, Document};
 use mongodb::options::{ClientOptions, ResolverConfig};
-use mongodb::{bson::doc, options::FindOptions};
 use mongodb::{Client, Collection};
-use serde::de::DeserializeOwned;
 use std::env;
 use std::error::Error;
 
@@ -14,11 +15,14 @@ pub struct Db {
 }
 
 impl Db {
-    pub async fn new() -> Result<Db, Box<dyn Error>> {
+    pub async fn new(uri: Option<String>) -> Result<Db, Box<dyn Error>> {
         dotenv().ok();
 
-        let client_uri =
-            env::var("MONGODB_URI").expect("You must set the MONGODB_URI environment var!");
+        let client_uri = match uri {
+            Some(uri) => uri,
+            None => env::var("MONGODB_URI")
+                .map_err(|_| "You must set the MONGODB_URI environment var!")?,
+        };
 
         let options: ClientOptions =
             ClientOptions::parse_with_resolver_config(&client_uri, ResolverConfig::cloudflare())
@@ -28,23 +32,84 @@ impl Db {
         Ok(Db { client })
     }
 
-    pub async fn generate_random_path<T: DeserializeOwned + Unpin + std::marker::Send + Sync>(
-        &self,
-        collection: &Collection<T>,
-    ) -> Result<String, Box<dyn Error>> {
-        // Generate random path then check for existing random path
-        // before inserting record
-        let random_path = crate::utils::generate_random_path();
-
-        let filter = doc! { "random_path": &random_path };
-        let find_options = FindOptions::builder().build();
-        let mut cursor = collection.find(filter, find_options).await?;
-
-        // if there is an existing timesheet with the random path, generate again
-        if let Some(_) = cursor.try_next().await? {
-            &self.generate_random_path(collection);
+    fn collection(&self) -> Collection<Document> {
+        self.client
+            .database("timesheet-gen")
+            .collection("timesheet-temp-paths")
+    }
+
+    async fn create_expiration_index(&self) -> Result<(), Box<dyn Error>> {
+        // Check for existing index for TTL on the collection
+        let index_names = self.collection().list_index_names().await?;
+
+        if !index_names.contains(&String::from("expiration_date")) {
+            // create TTL index to expire documents after 30 minutes
+            self.client
+                .database("timesheet-gen")
+                .run_command(
+                    doc! {
+                        "createIndexes": "timesheet-temp-paths",
+                        "indexes": [
+                            {
+                                "key": { "creation_date": 1 },
+                                "name": "expiration_date",
+                                "expireAfterSeconds": EXPIRE_TIME_SECONDS,
+                                "unique": true
+                            },
+                        ]
+                    },
+                    None,
+                )
+                .await?;
         }
 
-        Ok(random_path)
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TimesheetStore for Db {
+    async fn publish(&self, document: &TimesheetDocument) -> Result<String, Box<dyn Error>> {
+        self.create_expiration_index().await?;
+
+        // the TTL index only applies to BSON dates
+        let mut timesheet = bson::to_document(document)?;
+        timesheet.insert(
+            "creation_date",
+            bson::DateTime::from_chrono(document.creation_date),
+        );
+        self.collection().insert_one(timesheet, None).await?;
+
+        Ok(format!("https://timesheet-gen.io/{}", document.random_path))
+    }
+
+    async fn fetch(&self, random_path: &str) -> Result<Option<TimesheetDocument>, Box<dyn Error>> {
+        let filter = doc! { "random_path": random_path };
+
+        match self.collection().find_one(filter, None).await? {
+            Some(mut timesheet) => {
+                let creation_date = timesheet.get_datetime("creation_date")?.to_chrono();
+                timesheet.insert("creation_date", Bson::String(creation_date.to_rfc3339()));
+                Ok(Some(bson::from_document(timesheet)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn expire(&self) -> Result<u64, Box<dyn Error>> {
+        // the TTL index removes expired timesheets in the background,
+        // this removes any that it hasn't got to yet
+        let expiry_date = Utc::now() - Duration::seconds(EXPIRE_TIME_SECONDS);
+        let filter = doc! { "creation_date": { "$lt": bson::DateTime::from_chrono(expiry_date) } };
+
+        let result = self.collection().delete_many(filter, None).await?;
+        Ok(result.deleted_count)
+    }
+
+    async fn revoke(&self, random_path: &str) -> Result<bool, Box<dyn Error>> {
+        let filter = doc! { "random_path": random_path };
+
+        let result = self.collection().delete_one(filter, None).await?;
+        Ok(result.deleted_count > 0)
     }
 }
@@ -0,0 +1,440 @@
+use crate::period::Period;
+use crate::timesheet::{DayOverride, Hours, Timesheet};
+use chrono::{Datelike, Duration, NaiveDate};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{self, Write};
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const CELL_WIDTH: usize = 10;
+const HELP: &str =
+    "arrows move  0-9 hours  space on/off  n note  r reset day  s save  q quit without saving";
+
+enum Mode {
+    Browse,
+    Hours(String),
+    Note(String),
+}
+
+#[derive(PartialEq, Debug)]
+enum Outcome {
+    Save,
+    Quit,
+}
+
+// A full screen calendar for reviewing the days found in the git log, the changes
+// being kept as overrides in the same way as 'timesheet-gen edit'
+pub struct Editor {
+    title: String,
+    period: Period,
+    // the days found in the git log, before any edits
+    base: Timesheet,
+    overrides: BTreeMap<NaiveDate, DayOverride>,
+    // hours given to a day that is switched on and has no commits
+    default_hours: Hours,
+    cursor: NaiveDate,
+    mode: Mode,
+    message: Option<String>,
+}
+
+impl Editor {
+    pub fn new(
+        title: String,
+        period: Period,
+        base: Timesheet,
+        overrides: BTreeMap<NaiveDate, DayOverride>,
+        default_hours: Hours,
+    ) -> Editor {
+        Editor {
+            title,
+            period,
+            base,
+            overrides,
+            default_hours,
+            cursor: period.from,
+            mode: Mode::Browse,
+            message: None,
+        }
+    }
+
+    // The timesheet as it will be made, with the edits applied
+    fn timesheet(&self) -> Timesheet {
+        let mut timesheet = self.base.clone();
+        for (date, edit) in self.overrides.range(self.period.from..=self.period.to) {
+            timesheet.apply_override(*date, edit);
+        }
+        timesheet
+    }
+
+    fn is_worked(timesheet: &Timesheet, date: NaiveDate) -> bool {
+        Editor::hours_on(timesheet, date).is_some()
+    }
+
+    fn hours_on(timesheet: &Timesheet, date: NaiveDate) -> Option<Hours> {
+        timesheet
+            .months
+            .iter()
+            .find(|sheet| sheet.year == date.year() && sheet.month == date.month())
+            .and_then(|sheet| sheet.days.get(&date))
+            .map(|day| day.hours)
+    }
+
+    fn note_on(timesheet: &Timesheet, date: NaiveDate) -> Option<String> {
+        timesheet
+            .months
+            .iter()
+            .find(|sheet| sheet.year == date.year() && sheet.month == date.month())
+            .and_then(|sheet| sheet.days.get(&date))
+            .and_then(|day| day.note.clone())
+    }
+
+    fn edit(&mut self, edit: DayOverride) {
+        self.overrides.entry(self.cursor).or_default().merge(edit);
+    }
+
+    fn move_cursor(&mut self, days: i64) {
+        let date = self.cursor + Duration::days(days);
+        if self.period.contains(&date) {
+            self.cursor = date;
+        }
+    }
+
+    // Switch the day off, or back on with the hours from the git log if it
+    // had commits, otherwise with the default hours
+    fn toggle(&mut self) {
+        if Editor::is_worked(&self.timesheet(), self.cursor) {
+            self.edit(DayOverride {
+                removed: true,
+                ..Default::default()
+            });
+        } else if Editor::is_worked(&self.base, self.cursor) {
+            self.overrides.remove(&self.cursor);
+        } else {
+            self.edit(DayOverride {
+                hours: Some(self.default_hours),
+                added: true,
+                ..Default::default()
+            });
+        }
+    }
+
+    fn set_hours(&mut self, input: &str) {
+        match input.parse::<f64>() {
+            Ok(hours) if (0.0..=24.0).contains(&hours) => self.edit(DayOverride {
+                hours: Some(Hours::from_hours(hours)),
+                added: true,
+                ..Default::default()
+            }),
+            _ => self.message = Some(format!("{} isn't a number of hours in a day", input)),
+        }
+    }
+
+    fn set_note(&mut self, note: String) {
+        if !note.is_empty() {
+            self.edit(DayOverride {
+                note: Some(note),
+                ..Default::default()
+            });
+        } else if let Some(edit) = self.overrides.get_mut(&self.cursor) {
+            edit.note = None;
+        }
+    }
+
+    fn handle(&mut self, key: KeyEvent) -> Option<Outcome> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Some(Outcome::Quit);
+        }
+        self.message = None;
+
+        match &mut self.mode {
+            Mode::Browse => match key.code {
+                KeyCode::Left | KeyCode::Char('h') => self.move_cursor(-1),
+                KeyCode::Right | KeyCode::Char('l') => self.move_cursor(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-7),
+                KeyCode::Down | KeyCode::Char('j') => self.move_cursor(7),
+                KeyCode::Char(digit @ ('0'..='9' | '.')) => {
+                    self.mode = Mode::Hours(digit.to_string())
+                }
+                KeyCode::Char(' ') => self.toggle(),
+                KeyCode::Char('n') => {
+                    let note = Editor::note_on(&self.timesheet(), self.cursor);
+                    self.mode = Mode::Note(note.unwrap_or_default());
+                }
+                KeyCode::Char('r') => {
+                    self.overrides.remove(&self.cursor);
+                }
+                KeyCode::Char('s') => return Some(Outcome::Save),
+                KeyCode::Char('q') | KeyCode::Esc => return Some(Outcome::Quit),
+                _ => {}
+            },
+            Mode::Hours(input) => match key.code {
+                KeyCode::Char(digit @ ('0'..='9' | '.')) => input.push(digit),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let input = input.clone();
+                    self.mode = Mode::Browse;
+                    self.set_hours(&input);
+                }
+                KeyCode::Esc => self.mode = Mode::Browse,
+                _ => {}
+            },
+            Mode::Note(input) => match key.code {
+                KeyCode::Char(character) => input.push(character),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let note = input.trim().to_string();
+                    self.mode = Mode::Browse;
+                    self.set_note(note);
+                }
+                KeyCode::Esc => self.mode = Mode::Browse,
+                _ => {}
+            },
+        }
+
+        None
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let timesheet = self.timesheet();
+        let first = self.cursor.with_day(1).unwrap();
+        let month = Period::current_month(first);
+
+        queue!(out, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        let mut lines: Vec<Vec<(String, Option<Attribute>)>> = vec![
+            vec![(self.title.clone(), Some(Attribute::Bold))],
+            vec![(first.format("%B %Y").to_string(), None)],
+            vec![],
+        ];
+
+        let mut header = String::new();
+        for weekday in WEEKDAYS {
+            header += &format!("{:>width$}", weekday, width = CELL_WIDTH);
+        }
+        lines.push(vec![(
+            header + &format!("{:>8}", "Total"),
+            Some(Attribute::Bold),
+        )]);
+
+        let mut week = vec![(
+            " ".repeat(CELL_WIDTH * first.weekday().num_days_from_monday() as usize),
+            None,
+        )];
+        let mut week_total = Hours::default();
+        let mut date = month.from;
+        while date <= month.to {
+            let hours = Editor::hours_on(&timesheet, date);
+            week_total += hours.unwrap_or_default();
+
+            let edited = match self.overrides.contains_key(&date) {
+                true => "*",
+                false => " ",
+            };
+            let cell = match hours {
+                Some(hours) => format!("{:>2} {:>5}{}", date.day(), hours, edited),
+                None => format!("{:>2} {:>5}{}", date.day(), "-", edited),
+            };
+            let flagged = timesheet
+                .months
+                .iter()
+                .find_map(|sheet| sheet.days.get(&date))
+                .and_then(|day| day.day_off.as_ref())
+                .is_some_and(|day_off| day_off.flagged);
+            let attribute = if date == self.cursor {
+                Some(Attribute::Reverse)
+            } else if !self.period.contains(&date) {
+                Some(Attribute::Dim)
+            } else if flagged {
+                Some(Attribute::Underlined)
+            } else {
+                None
+            };
+            week.push((
+                format!("{:>width$}", cell, width = CELL_WIDTH - 1),
+                attribute,
+            ));
+            week.push((" ".to_string(), None));
+
+            if date.weekday().num_days_from_monday() == 6 || date == month.to {
+                let padding = 6 - date.weekday().num_days_from_monday() as usize;
+                week.push((
+                    format!("{}{:>8}", " ".repeat(padding * CELL_WIDTH), week_total),
+                    None,
+                ));
+                lines.push(week);
+                week = vec![];
+                week_total = Hours::default();
+            }
+            date = date.succ_opt().unwrap();
+        }
+
+        let month_days: Vec<Hours> = timesheet
+            .months
+            .iter()
+            .filter(|sheet| sheet.year == first.year() && sheet.month == first.month())
+            .flat_map(|sheet| sheet.days.values().map(|day| day.hours))
+            .collect();
+        lines.push(vec![]);
+        lines.push(vec![(
+            format!(
+                "{}: {} hours over {} days    {}: {} hours",
+                first.format("%B"),
+                month_days.iter().copied().sum::<Hours>(),
+                month_days
+                    .iter()
+                    .filter(|hours| **hours > Hours::default())
+                    .count(),
+                self.period,
+                timesheet.total_hours()
+            ),
+            Some(Attribute::Bold),
+        )]);
+
+        let mut selected = self.cursor.format("%A %-d %B").to_string();
+        let day = timesheet
+            .months
+            .iter()
+            .find_map(|sheet| sheet.days.get(&self.cursor));
+        if let Some(remarks) = day.and_then(|day| day.remarks()) {
+            selected += &format!(" - {}", remarks);
+        }
+        if day
+            .and_then(|day| day.day_off.as_ref())
+            .is_some_and(|day_off| day_off.flagged)
+        {
+            selected += " (flagged for review)";
+        }
+        lines.push(vec![(selected, None)]);
+        lines.push(vec![]);
+
+        lines.push(vec![match (&self.mode, &self.message) {
+            (Mode::Hours(input), _) => (format!("Hours: {}_", input), None),
+            (Mode::Note(input), _) => (format!("Note: {}_", input), None),
+            (Mode::Browse, Some(message)) => (message.clone(), Some(Attribute::Bold)),
+            (Mode::Browse, None) => (HELP.to_string(), Some(Attribute::Dim)),
+        }]);
+
+        for (row, line) in lines.into_iter().enumerate() {
+            queue!(out, cursor::MoveTo(0, row as u16))?;
+            for (text, attribute) in line {
+                match attribute {
+                    Some(attribute) => queue!(
+                        out,
+                        SetAttribute(attribute),
+                        Print(text),
+                        SetAttribute(Attribute::Reset)
+                    )?,
+                    None => queue!(out, Print(text))?,
+                }
+            }
+        }
+
+        out.flush()
+    }
+
+    // Run the editor until it is saved or quit, returning the overrides if saved
+    pub fn run(mut self) -> Result<Option<BTreeMap<NaiveDate, DayOverride>>, Box<dyn Error>> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        let outcome = (|| -> Result<Outcome, Box<dyn Error>> {
+            loop {
+                self.draw(&mut out)?;
+                if let Event::Key(key) = event::read()? {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    if let Some(outcome) = self.handle(key) {
+                        return Ok(outcome);
+                    }
+                }
+            }
+        })();
+
+        // always give the terminal back, even if drawing failed
+        execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+
+        match outcome? {
+            Outcome::Save => Ok(Some(self.overrides)),
+            Outcome::Quit => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timesheet::DayEntry;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn type_keys(editor: &mut Editor, keys: &str) -> Option<Outcome> {
+        keys.chars()
+            .map(|character| match character {
+                '\n' => key(KeyCode::Enter),
+                '>' => key(KeyCode::Right),
+                'v' => key(KeyCode::Down),
+                character => key(KeyCode::Char(character)),
+            })
+            .filter_map(|key| editor.handle(key))
+            .last()
+    }
+
+    #[test]
+    fn it_edits_days_from_the_keyboard() {
+        let september = |day| NaiveDate::from_ymd_opt(2021, 9, day).unwrap();
+        let mut base = Timesheet::new();
+        base.insert_day(september(1), DayEntry::new(Hours::from_hours(8.0)));
+        base.insert_day(september(2), DayEntry::new(Hours::from_hours(8.0)));
+
+        let mut editor = Editor::new(
+            "timesheet".to_string(),
+            Period::month(2021, 9).unwrap(),
+            base,
+            BTreeMap::new(),
+            Hours::from_hours(7.5),
+        );
+
+        // 5.5 hours with a note on the 1st, switch off the 2nd, switch on the 9th
+        assert_eq!(type_keys(&mut editor, "5.5\nnworkshop\n> v "), None);
+        assert_eq!(editor.cursor, september(9));
+
+        let timesheet = editor.timesheet();
+        assert_eq!(
+            Editor::hours_on(&timesheet, september(1)),
+            Some(Hours::from_hours(5.5))
+        );
+        assert_eq!(
+            Editor::note_on(&timesheet, september(1)).as_deref(),
+            Some("workshop")
+        );
+        assert_eq!(Editor::hours_on(&timesheet, september(2)), None);
+        assert_eq!(
+            Editor::hours_on(&timesheet, september(9)),
+            Some(Hours::from_hours(7.5))
+        );
+
+        // switching the 2nd back on restores the hours from the git log
+        editor.cursor = september(2);
+        type_keys(&mut editor, " ");
+        assert!(!editor.overrides.contains_key(&september(2)));
+
+        // the cursor stays within the period
+        editor.cursor = september(30);
+        type_keys(&mut editor, ">v");
+        assert_eq!(editor.cursor, september(30));
+
+        assert_eq!(type_keys(&mut editor, "s"), Some(Outcome::Save));
+    }
+}
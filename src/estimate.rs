@@ -0,0 +1,178 @@
+use crate::timesheet::Hours;
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+// Turns the commit times for a single day into the hours worked that day
+pub trait EstimateHours {
+    fn estimate(&self, commit_times: &[DateTime<FixedOffset>]) -> Hours;
+}
+
+// The same number of hours for every day with a commit
+pub struct FlatRate {
+    pub hours: f64,
+}
+
+impl EstimateHours for FlatRate {
+    fn estimate(&self, _commit_times: &[DateTime<FixedOffset>]) -> Hours {
+        Hours::from_hours(self.hours)
+    }
+}
+
+// Groups commits into sessions, where a session ends when the gap to the next
+// commit is longer than max_gap_minutes. Each session is counted from its first
+// commit to its last, plus lead_in_minutes for the work before the first commit
+pub struct Sessions {
+    pub max_gap_minutes: u32,
+    pub lead_in_minutes: u32,
+}
+
+impl EstimateHours for Sessions {
+    fn estimate(&self, commit_times: &[DateTime<FixedOffset>]) -> Hours {
+        let mut times = commit_times.to_vec();
+        times.sort();
+
+        let mut minutes = 0;
+        let mut session_start = match times.first() {
+            Some(time) => *time,
+            None => return Hours::default(),
+        };
+        let mut previous = session_start;
+
+        for time in times.iter().skip(1) {
+            if (*time - previous).num_minutes() > self.max_gap_minutes as i64 {
+                minutes += (previous - session_start).num_minutes() + self.lead_in_minutes as i64;
+                session_start = *time;
+            }
+            previous = *time;
+        }
+        minutes += (previous - session_start).num_minutes() + self.lead_in_minutes as i64;
+
+        Hours::from_minutes(minutes as u32)
+    }
+}
+
+// Counts the day from the first commit to the last, plus lead_in_minutes
+pub struct Span {
+    pub lead_in_minutes: u32,
+}
+
+impl EstimateHours for Span {
+    fn estimate(&self, commit_times: &[DateTime<FixedOffset>]) -> Hours {
+        match (commit_times.iter().min(), commit_times.iter().max()) {
+            (Some(first), Some(last)) => {
+                Hours::from_minutes((*last - *first).num_minutes() as u32 + self.lead_in_minutes)
+            }
+            _ => Hours::default(),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum Strategy {
+    Flat {
+        hours: f64,
+    },
+    Session {
+        max_gap_minutes: u32,
+        lead_in_minutes: u32,
+    },
+    Span {
+        lead_in_minutes: u32,
+    },
+}
+
+impl Strategy {
+    pub fn estimator(&self) -> Box<dyn EstimateHours> {
+        match self {
+            Strategy::Flat { hours } => Box::new(FlatRate { hours: *hours }),
+            Strategy::Session {
+                max_gap_minutes,
+                lead_in_minutes,
+            } => Box::new(Sessions {
+                max_gap_minutes: *max_gap_minutes,
+                lead_in_minutes: *lead_in_minutes,
+            }),
+            Strategy::Span { lead_in_minutes } => Box::new(Span {
+                lead_in_minutes: *lead_in_minutes,
+            }),
+        }
+    }
+}
+
+// How hours are estimated, as stored in the user config e.g.
+// { "strategy": "session", "max_gap_minutes": 120, "lead_in_minutes": 30, "daily_cap_hours": 8 }
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct HoursConfig {
+    #[serde(flatten)]
+    pub strategy: Strategy,
+    pub daily_cap_hours: f64,
+}
+
+impl Default for HoursConfig {
+    fn default() -> HoursConfig {
+        HoursConfig {
+            strategy: Strategy::Flat { hours: 8.0 },
+            daily_cap_hours: 8.0,
+        }
+    }
+}
+
+impl HoursConfig {
+    pub fn estimate(&self, commit_times: &[DateTime<FixedOffset>]) -> Hours {
+        let hours = self.strategy.estimator().estimate(commit_times);
+        hours.min(Hours::from_hours(self.daily_cap_hours))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(times: &[&str]) -> Vec<DateTime<FixedOffset>> {
+        times
+            .iter()
+            .map(|time| DateTime::parse_from_rfc3339(time).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn it_groups_commits_into_sessions() {
+        let estimator = Sessions {
+            max_gap_minutes: 60,
+            lead_in_minutes: 30,
+        };
+        let commit_times = times(&[
+            "2021-10-04T14:00:00+01:00",
+            "2021-10-04T09:00:00+01:00",
+            "2021-10-04T09:45:00+01:00",
+            "2021-10-04T10:30:00+01:00",
+        ]);
+
+        // 09:00 - 10:30 plus lead in, and 14:00 plus lead in
+        assert_eq!(
+            estimator.estimate(&commit_times),
+            Hours::from_minutes(90 + 30 + 30)
+        );
+    }
+
+    #[test]
+    fn it_counts_the_span_between_the_first_and_last_commit() {
+        let estimator = Span { lead_in_minutes: 0 };
+        let commit_times = times(&["2021-10-04T17:15:00+01:00", "2021-10-04T09:00:00+01:00"]);
+
+        assert_eq!(estimator.estimate(&commit_times), Hours::from_minutes(495));
+        assert_eq!(estimator.estimate(&[]), Hours::default());
+    }
+
+    #[test]
+    fn it_caps_the_estimate_for_a_day() {
+        let config: HoursConfig = serde_json::from_str(
+            r#"{ "strategy": "span", "lead_in_minutes": 60, "daily_cap_hours": 7.5 }"#,
+        )
+        .unwrap();
+        let commit_times = times(&["2021-10-04T08:00:00+01:00", "2021-10-04T18:00:00+01:00"]);
+
+        assert_eq!(config.estimate(&commit_times), Hours::from_hours(7.5));
+    }
+}
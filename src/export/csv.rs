@@ -0,0 +1,167 @@
+use super::{category_summary, day_tickets, invoice_totals, ticket_summary};
+use crate::period::Period;
+use crate::repo::Repo;
+use crate::timesheet::{EntryKind, Hours};
+use std::collections::BTreeMap;
+
+// Quote a field if it contains anything that would break the row
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn row(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields.iter().map(|value| field(value)).collect();
+    fields.join(",") + "\r\n"
+}
+
+// A day's time logged by hand e.g. "meeting 2; travel 1.5"
+fn day_logged(logged: &BTreeMap<EntryKind, Hours>) -> String {
+    let logged: Vec<String> = logged
+        .iter()
+        .map(|(kind, hours)| format!("{} {}", kind.label().to_lowercase(), hours))
+        .collect();
+    logged.join("; ")
+}
+
+// One row per day worked in the period, followed by a totals row, then the
+// time per category if any was logged by hand, the hours per ticket if any
+// commits referred to a ticket and the money owed if the project has a rate
+pub fn render(repo: &Repo, period: &Period) -> String {
+    let mut csv = row(&[
+        "date",
+        "weekday",
+        "hours",
+        "project",
+        "repositories",
+        "notes",
+        "tickets",
+        "day_off",
+        "logged",
+        "leave",
+    ]);
+
+    let mut total = Hours::default();
+    for sheet in repo.timesheet.months.iter() {
+        for (date, day) in sheet.days.iter().filter(|(date, _)| period.contains(date)) {
+            let repositories: Vec<&str> = day.repositories.iter().map(|r| r.as_str()).collect();
+            csv += &row(&[
+                &date.format("%Y-%m-%d").to_string(),
+                &date.format("%A").to_string(),
+                &day.hours.to_string(),
+                &repo.namespace,
+                &repositories.join("; "),
+                day.note.as_deref().unwrap_or(""),
+                &day_tickets(&day.tickets),
+                &day.day_off
+                    .as_ref()
+                    .map(|day_off| day_off.to_string())
+                    .unwrap_or_default(),
+                &day_logged(&day.logged),
+                day.leave.map(|leave| leave.label()).unwrap_or(""),
+            ]);
+            total += day.hours;
+        }
+    }
+
+    csv += &row(&["Total", "", &total.to_string(), "", "", "", "", "", "", ""]);
+
+    let categories = category_summary(repo, period);
+    if !categories.is_empty() {
+        csv += "\r\n";
+        csv += &row(&["category", "hours", "days"]);
+    }
+    for (category, hours, days) in categories {
+        csv += &row(&[&category, &hours.to_string(), &days.to_string()]);
+    }
+
+    let tickets = ticket_summary(repo, period);
+    if !tickets.is_empty() {
+        csv += "\r\n";
+        csv += &row(&["ticket", "hours"]);
+    }
+    for (ticket, hours) in tickets {
+        csv += &row(&[&ticket, &hours.to_string()]);
+    }
+
+    if let Some(totals) = invoice_totals(repo, period) {
+        csv += "\r\n";
+        csv += &row(&["item", "amount"]);
+        for (label, amount) in totals.lines() {
+            csv += &row(&[&label, &amount]);
+        }
+    }
+
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timesheet::{DayEntry, Timesheet};
+    use chrono::NaiveDate;
+    use std::path::Path;
+
+    #[test]
+    fn it_renders_a_row_per_day_and_a_total() {
+        let mut timesheet = Timesheet::new();
+        timesheet.insert_day(
+            NaiveDate::from_ymd_opt(2021, 9, 14).unwrap(),
+            DayEntry {
+                hours: Hours::from_hours(5.5),
+                note: Some("Workshop, \"discovery\"".to_string()),
+                repositories: vec!["frontend".to_string(), "backend".to_string()]
+                    .into_iter()
+                    .collect(),
+                tickets: vec![("ABC-1".to_string(), Hours::from_hours(4.0))]
+                    .into_iter()
+                    .collect(),
+                day_off: None,
+                logged: vec![(EntryKind::Meeting, Hours::from_hours(1.5))]
+                    .into_iter()
+                    .collect(),
+                leave: None,
+            },
+        );
+        timesheet.insert_day(
+            NaiveDate::from_ymd_opt(2021, 9, 15).unwrap(),
+            DayEntry::new(Hours::from_hours(8.0)),
+        );
+        timesheet.insert_day(
+            NaiveDate::from_ymd_opt(2021, 10, 1).unwrap(),
+            DayEntry::new(Hours::from_hours(8.0)),
+        );
+        let repo = Repo::new(
+            Some("acme".to_string()),
+            Path::new("/path/to/acme/.git/"),
+            "Tom Jones".to_string(),
+            "sex_bomb@gmail.com".to_string(),
+            "".to_string(),
+            "".to_string(),
+            "".to_string(),
+            timesheet,
+        )
+        .unwrap();
+
+        let csv = render(&repo, &Period::parse_month("2021-09").unwrap());
+
+        assert_eq!(
+            csv,
+            "date,weekday,hours,project,repositories,notes,tickets,day_off,logged,leave\r\n\
+             2021-09-14,Tuesday,5.5,acme,backend; frontend,\"Workshop, \"\"discovery\"\"\",ABC-1 4,,meeting 1.5,\r\n\
+             2021-09-15,Wednesday,8,acme,,,,,,\r\n\
+             Total,,13.5,,,,,,,\r\n\
+             \r\n\
+             category,hours,days\r\n\
+             Development,12,2\r\n\
+             Meeting,1.5,1\r\n\
+             \r\n\
+             ticket,hours\r\n\
+             ABC-1,4\r\n\
+             No ticket,8\r\n"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use super::{category_summary, day_tickets, invoice_totals, ticket_summary};
+use crate::period::Period;
+use crate::repo::Repo;
+use crate::timesheet::{DayEntry, Hours};
+use chrono::NaiveDate;
+
+const STYLE: &str = "
+body { font-family: Helvetica, Arial, sans-serif; color: #222; margin: 2em auto; max-width: 50em; }
+h1 { margin-bottom: 0; }
+h2 { margin-top: 2em; border-bottom: 1px solid #ccc; }
+.details { display: flex; gap: 4em; margin-top: 2em; }
+.details p { margin: 0.2em 0; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 0.3em 0.6em; border-bottom: 1px solid #eee; }
+td.hours, th.hours { text-align: right; }
+tfoot td { font-weight: bold; border-top: 2px solid #222; }
+.muted { color: #777; }
+table.totals { width: auto; margin-left: auto; }
+tr.grand-total td { font-weight: bold; border-top: 2px solid #222; }
+tr.leave td { color: #666; font-style: italic; }
+@media print { body { margin: 0; max-width: none; } h2 { page-break-after: avoid; } table { page-break-inside: auto; } }
+";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// A paragraph per line, skipping any that are empty
+fn paragraphs<'a>(lines: impl Iterator<Item = &'a str>) -> String {
+    lines
+        .map(|line| line.trim().trim_end_matches(','))
+        .filter(|line| !line.is_empty())
+        .map(|line| format!("<p>{}</p>\n", escape(line)))
+        .collect()
+}
+
+fn day_row(date: &NaiveDate, day: &DayEntry) -> String {
+    let repositories: Vec<&str> = day.repositories.iter().map(|r| r.as_str()).collect();
+    format!(
+        "<tr{}><td>{}</td><td>{}</td><td class=\"hours\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+        match day.leave {
+            Some(_) => " class=\"leave\"",
+            None => "",
+        },
+        date.format("%-d %B"),
+        date.format("%A"),
+        day.hours,
+        escape(&repositories.join(", ")),
+        escape(&day.remarks().unwrap_or_default()),
+        escape(&day_tickets(&day.tickets))
+    )
+}
+
+// A single html file with the styles inline, so it can be viewed offline or printed
+pub fn render(repo: &Repo, period: &Period) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Timesheet for {}: {}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        escape(&repo.namespace),
+        period,
+        STYLE
+    );
+
+    html += &format!(
+        "<h1>Timesheet</h1>\n<p class=\"muted\">{} &middot; {}</p>\n",
+        escape(&repo.namespace),
+        period
+    );
+
+    html += "<div class=\"details\">\n<div>\n<h3>Client</h3>\n";
+    html += &paragraphs(
+        [repo.client_name.as_str(), repo.contact_person.as_str()]
+            .iter()
+            .copied()
+            .chain(repo.address.lines()),
+    );
+    html += "</div>\n<div>\n<h3>Contractor</h3>\n";
+    html += &paragraphs([repo.name.as_str(), repo.email.as_str()].iter().copied());
+    html += &format!(
+        "<p class=\"muted\">{}</p>\n</div>\n</div>\n",
+        escape(&repo.path)
+    );
+
+    let mut total = Hours::default();
+    for sheet in repo.timesheet.months.iter() {
+        let days: Vec<_> = sheet
+            .days
+            .iter()
+            .filter(|(date, _)| period.contains(date))
+            .collect();
+        if days.is_empty() {
+            continue;
+        }
+
+        let first = NaiveDate::from_ymd_opt(sheet.year, sheet.month, 1).unwrap();
+        html += &format!(
+            "<h2>{}</h2>\n<table>\n<thead><tr><th>Date</th><th>Day</th>\
+             <th class=\"hours\">Hours</th><th>Repositories</th><th>Notes</th><th>Tickets</th></tr></thead>\n<tbody>\n",
+            first.format("%B %Y")
+        );
+
+        let mut month_total = Hours::default();
+        for (date, day) in days {
+            html += &day_row(date, day);
+            month_total += day.hours;
+        }
+        total += month_total;
+
+        html += &format!(
+            "</tbody>\n<tfoot><tr><td colspan=\"2\">Total</td><td class=\"hours\">{}</td>\
+             <td colspan=\"3\"></td></tr></tfoot>\n</table>\n",
+            month_total
+        );
+    }
+
+    let categories = category_summary(repo, period);
+    if !categories.is_empty() {
+        html += "<h2>Time by category</h2>\n<table>\n<thead><tr><th>Category</th>\
+                 <th class=\"hours\">Hours</th><th class=\"hours\">Days</th></tr></thead>\n<tbody>\n";
+        for (category, hours, days) in categories {
+            html += &format!(
+                "<tr><td>{}</td><td class=\"hours\">{}</td><td class=\"hours\">{}</td></tr>\n",
+                escape(&category),
+                hours,
+                days
+            );
+        }
+        html += "</tbody>\n</table>\n";
+    }
+
+    let tickets = ticket_summary(repo, period);
+    if !tickets.is_empty() {
+        html += "<h2>Hours by ticket</h2>\n<table>\n<thead><tr><th>Ticket</th>\
+                 <th class=\"hours\">Hours</th></tr></thead>\n<tbody>\n";
+        for (ticket, hours) in tickets {
+            html += &format!(
+                "<tr><td>{}</td><td class=\"hours\">{}</td></tr>\n",
+                escape(&ticket),
+                hours
+            );
+        }
+        html += "</tbody>\n</table>\n";
+    }
+
+    html += &format!(
+        "<h2>Total</h2>\n<p><strong>{} hours</strong> for {}</p>\n",
+        total, period
+    );
+
+    if let Some(totals) = invoice_totals(repo, period) {
+        html += "<table class=\"totals\">\n<tbody>\n";
+        let lines = totals.lines();
+        for (index, (label, amount)) in lines.iter().enumerate() {
+            let class = match index == lines.len() - 1 {
+                true => " class=\"grand-total\"",
+                false => "",
+            };
+            html += &format!(
+                "<tr{}><td>{}</td><td class=\"hours\">{}</td></tr>\n",
+                class,
+                escape(label),
+                escape(amount)
+            );
+        }
+        html += "</tbody>\n</table>\n";
+    }
+
+    html += "</body>\n</html>\n";
+
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timesheet::{DayEntry, Timesheet};
+
+    #[test]
+    fn it_renders_escaped_details_and_days_in_the_period() {
+        let mut timesheet = Timesheet::new();
+        let september = |day| NaiveDate::from_ymd_opt(2021, 9, day).unwrap();
+        timesheet.insert_day(september(6), DayEntry::new(Hours::from_hours(7.5)));
+        timesheet.insert_day(september(30), DayEntry::new(Hours::from_hours(8.0)));
+
+        let repo = Repo {
+            namespace: "timesheet".to_string(),
+            path: "/path/to/timesheet/.git/".to_string(),
+            name: "Tom Jones".to_string(),
+            email: "sex_bomb@gmail.com".to_string(),
+            client_name: "Smith & Sons <Ltd>".to_string(),
+            contact_person: "Jane Smith".to_string(),
+            address: "1 Road,\nTown".to_string(),
+            timesheet,
+            ..Default::default()
+        };
+        let period = Period::new(september(1), september(29)).unwrap();
+
+        let html = render(&repo, &period);
+        assert!(html.contains("<p>Smith &amp; Sons &lt;Ltd&gt;</p>"));
+        assert!(html.contains("<p>1 Road</p>\n<p>Town</p>"));
+        assert!(html.contains("<td>6 September</td><td>Monday</td><td class=\"hours\">7.5</td>"));
+        assert!(!html.contains("30 September"));
+        assert!(html.contains("<strong>7.5 hours</strong>"));
+        assert!(!html.contains("<link") && !html.contains("<script"));
+    }
+}
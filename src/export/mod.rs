@@ -0,0 +1,154 @@
+use crate::invoice::Invoice;
+use crate::period::Period;
+use crate::rates::Totals;
+use crate::repo::Repo;
+use crate::timesheet::{DayEntry, EntryKind, Hours};
+use clap::ArgEnum;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+mod csv;
+mod html;
+mod pdf;
+mod table;
+
+#[derive(ArgEnum, PartialEq, Debug, Clone, Copy, Default)]
+pub enum Format {
+    // Publish to the configured store, viewable at timesheet-gen.io
+    #[default]
+    Web,
+    Csv,
+    Html,
+    Pdf,
+    // A calendar table for the terminal
+    Text,
+    Markdown,
+}
+
+// Render the timesheet in the given format, returning None for formats that are published
+pub fn render(format: Format, repo: &Repo, period: &Period) -> Option<Vec<u8>> {
+    match format {
+        Format::Web => None,
+        Format::Csv => Some(csv::render(repo, period).into_bytes()),
+        Format::Html => Some(html::render(repo, period).into_bytes()),
+        Format::Pdf => Some(pdf::render(repo, period)),
+        Format::Text => Some(table::render_text(repo, period).into_bytes()),
+        Format::Markdown => Some(table::render_markdown(repo, period).into_bytes()),
+    }
+}
+
+// An issued invoice as a PDF
+pub fn render_invoice(invoice: &Invoice) -> Vec<u8> {
+    pdf::render_invoice(invoice)
+}
+
+// Binary formats aren't written to stdout, they default to a file in the current directory
+pub fn default_output(format: Format, repo: &Repo, period: &Period) -> Option<PathBuf> {
+    match format {
+        Format::Pdf => Some(PathBuf::from(format!(
+            "{}-timesheet-{}.pdf",
+            repo.namespace,
+            period.from.format("%Y-%m")
+        ))),
+        _ => None,
+    }
+}
+
+// The hours spent on each ticket in the period, followed by the hours from the
+// git log that aren't on a ticket. Time logged by hand is in the category summary
+// instead. Empty when no commits referred to a ticket
+fn ticket_summary(repo: &Repo, period: &Period) -> Vec<(String, Hours)> {
+    let mut tickets: BTreeMap<String, Hours> = BTreeMap::new();
+    let mut total = Hours::default();
+    for sheet in repo.timesheet.months.iter() {
+        for (_, day) in sheet.days.iter().filter(|(date, _)| period.contains(date)) {
+            for (ticket, hours) in day.tickets.iter() {
+                *tickets.entry(ticket.clone()).or_default() += *hours;
+            }
+            total += day.development_hours();
+        }
+    }
+    if tickets.is_empty() {
+        return vec![];
+    }
+
+    let ticketed: Hours = tickets.values().copied().sum();
+    let mut summary: Vec<(String, Hours)> = tickets.into_iter().collect();
+    if total > ticketed {
+        let untracked = total.as_minutes() - ticketed.as_minutes();
+        summary.push(("No ticket".to_string(), Hours::from_minutes(untracked)));
+    }
+    summary
+}
+
+// The hours and days spent on each category in the period, from the git log and
+// time logged by hand, followed by the days of each kind of leave. Empty when
+// nothing was logged by hand
+fn category_summary(repo: &Repo, period: &Period) -> Vec<(String, Hours, usize)> {
+    let days: Vec<&DayEntry> = repo
+        .timesheet
+        .months
+        .iter()
+        .flat_map(|sheet| sheet.days.iter())
+        .filter(|(date, _)| period.contains(date))
+        .map(|(_, day)| day)
+        .collect();
+    if days
+        .iter()
+        .all(|day| day.logged.is_empty() && day.leave.is_none())
+    {
+        return vec![];
+    }
+
+    // development first, then each kind of time logged by hand and leave
+    let mut summary: BTreeMap<Option<EntryKind>, (Hours, usize)> = BTreeMap::new();
+    for day in days.iter() {
+        let leave = day.leave.map(|kind| (Some(kind), Hours::default()));
+        for (kind, hours) in day.categories().into_iter().chain(leave) {
+            let (total, days) = summary.entry(kind).or_default();
+            *total += hours;
+            *days += 1;
+        }
+    }
+
+    summary
+        .into_iter()
+        .map(|(kind, (hours, days))| (EntryKind::category_label(kind).to_string(), hours, days))
+        .collect()
+}
+
+// What is owed for the days in the period, when the project has a rate
+fn invoice_totals(repo: &Repo, period: &Period) -> Option<Totals> {
+    let rates = repo.rates.as_ref()?;
+    let days = repo
+        .timesheet
+        .months
+        .iter()
+        .flat_map(|sheet| sheet.days.iter())
+        .filter(|(date, _)| period.contains(date))
+        .map(|(_, day)| day);
+
+    Some(rates.totals(days))
+}
+
+// A day's tickets as text e.g. "ABC-1 3; ABC-2 1.5"
+fn day_tickets(tickets: &BTreeMap<String, Hours>) -> String {
+    let tickets: Vec<String> = tickets
+        .iter()
+        .map(|(ticket, hours)| format!("{} {}", ticket, hours))
+        .collect();
+    tickets.join("; ")
+}
+
+// Write the rendered timesheet to a file, or to stdout when no file is given
+pub fn write(rendered: &[u8], output: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    match output {
+        Some(path) => fs::write(path, rendered)?,
+        None => io::stdout().write_all(rendered)?,
+    }
+
+    Ok(())
+}
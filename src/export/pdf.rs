@@ -0,0 +1,733 @@
+use super::{category_summary, invoice_totals, ticket_summary};
+use crate::invoice::{Invoice, Party};
+use crate::period::Period;
+use crate::rates::Totals;
+use crate::repo::Repo;
+use crate::timesheet::{Hours, MonthSheet};
+use chrono::{Datelike, NaiveDate};
+
+// A4 in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const CELL_HEIGHT: f32 = 56.0;
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+enum Font {
+    Regular,
+    Bold,
+}
+
+// The drawing operations for a single page
+struct Page {
+    content: String,
+}
+
+impl Page {
+    fn new() -> Page {
+        Page {
+            content: String::new(),
+        }
+    }
+
+    fn text(&mut self, x: f32, y: f32, size: f32, font: Font, text: &str) {
+        let font = match font {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        };
+        self.content += &format!(
+            "BT /{} {} Tf {:.2} {:.2} Td ({}) Tj ET\n",
+            font,
+            size,
+            x,
+            y,
+            escape(text)
+        );
+    }
+
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, grey: Option<f32>) {
+        match grey {
+            Some(grey) => {
+                self.content += &format!(
+                    "{} g {:.2} {:.2} {:.2} {:.2} re B 0 g\n",
+                    grey, x, y, width, height
+                )
+            }
+            None => self.content += &format!("{:.2} {:.2} {:.2} {:.2} re S\n", x, y, width, height),
+        }
+    }
+
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        self.content += &format!("{:.2} {:.2} m {:.2} {:.2} l S\n", x1, y1, x2, y2);
+    }
+}
+
+// The characters WinAnsi encodes at 0x80 to 0x9F, where Latin-1 has control codes
+const WIN_ANSI_EXTRAS: [(char, u8); 27] = [
+    ('€', 0x80),
+    ('‚', 0x82),
+    ('ƒ', 0x83),
+    ('„', 0x84),
+    ('…', 0x85),
+    ('†', 0x86),
+    ('‡', 0x87),
+    ('ˆ', 0x88),
+    ('‰', 0x89),
+    ('Š', 0x8a),
+    ('‹', 0x8b),
+    ('Œ', 0x8c),
+    ('Ž', 0x8e),
+    ('‘', 0x91),
+    ('’', 0x92),
+    ('“', 0x93),
+    ('”', 0x94),
+    ('•', 0x95),
+    ('–', 0x96),
+    ('—', 0x97),
+    ('˜', 0x98),
+    ('™', 0x99),
+    ('š', 0x9a),
+    ('›', 0x9b),
+    ('œ', 0x9c),
+    ('ž', 0x9e),
+    ('Ÿ', 0x9f),
+];
+
+// Strings use the standard fonts' WinAnsi encoding, which matches Latin-1 for
+// the printable characters and adds € and typographic punctuation. Anything
+// else is replaced
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for character in text.chars() {
+        match character {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            ' '..='~' => escaped.push(character),
+            '\u{a0}'..='\u{ff}' => escaped += &format!("\\{:03o}", character as u32),
+            _ => match WIN_ANSI_EXTRAS
+                .iter()
+                .find(|(extra, _)| *extra == character)
+            {
+                Some((_, code)) => escaped += &format!("\\{:03o}", code),
+                None => escaped.push('?'),
+            },
+        }
+    }
+    escaped
+}
+
+// Approximate width of Helvetica text, for right aligning and centring
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * size * 0.52
+}
+
+// Write out the pages as a PDF file, with cross reference table
+fn write_document(pages: &[Page]) -> Vec<u8> {
+    let mut objects: Vec<String> = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        String::new(), // the page tree, filled in once the page objects are numbered
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+
+    let mut page_ids = vec![];
+    for page in pages {
+        let content_id = objects.len() + 2;
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH, PAGE_HEIGHT, content_id
+        ));
+        page_ids.push(format!("{} 0 R", objects.len()));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            page.content.len(),
+            page.content
+        ));
+    }
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        page_ids.join(" "),
+        page_ids.len()
+    );
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = vec![];
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf += &format!("{} 0 obj\n{}\nendobj\n", index + 1, object);
+    }
+
+    let xref_offset = pdf.len();
+    pdf += &format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        pdf += &format!("{:010} 00000 n \n", offset);
+    }
+    pdf += &format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_offset
+    );
+
+    pdf.into_bytes()
+}
+
+// Client and contractor details at the top of every page, returning
+// the height that is left for the page content
+fn draw_header(page: &mut Page, repo: &Repo, period: &Period) -> f32 {
+    let mut y = PAGE_HEIGHT - MARGIN - 20.0;
+    page.text(MARGIN, y, 20.0, Font::Bold, "Timesheet");
+    let period = period.to_string();
+    page.text(
+        PAGE_WIDTH - MARGIN - text_width(&period, 12.0),
+        y,
+        12.0,
+        Font::Regular,
+        &period,
+    );
+
+    y -= 30.0;
+    let top = y;
+    page.text(MARGIN, y, 10.0, Font::Bold, "Client");
+    y -= 14.0;
+    let client_lines = [repo.client_name.as_str(), repo.contact_person.as_str()];
+    for line in client_lines
+        .iter()
+        .copied()
+        .chain(repo.address.lines().map(|line| line.trim_end_matches(',')))
+        .filter(|line| !line.is_empty())
+    {
+        page.text(MARGIN, y, 10.0, Font::Regular, line);
+        y -= 13.0;
+    }
+
+    let x = PAGE_WIDTH / 2.0;
+    let mut contractor_y = top;
+    page.text(x, contractor_y, 10.0, Font::Bold, "Contractor");
+    for line in [
+        repo.name.as_str(),
+        repo.email.as_str(),
+        repo.namespace.as_str(),
+    ] {
+        contractor_y -= 14.0;
+        page.text(x, contractor_y, 10.0, Font::Regular, line);
+    }
+
+    y.min(contractor_y) - 20.0
+}
+
+// A calendar for the month with the hours worked in each day's cell.
+// Days outside of the period are shaded
+fn draw_month(page: &mut Page, sheet: &MonthSheet, period: &Period, top: f32) -> f32 {
+    let first = NaiveDate::from_ymd_opt(sheet.year, sheet.month, 1).unwrap();
+    let month = Period::current_month(first);
+    let cell_width = (PAGE_WIDTH - MARGIN * 2.0) / 7.0;
+
+    page.text(
+        MARGIN,
+        top,
+        14.0,
+        Font::Bold,
+        &first.format("%B %Y").to_string(),
+    );
+
+    let mut y = top - 22.0;
+    for (column, weekday) in WEEKDAYS.iter().enumerate() {
+        page.text(
+            MARGIN + column as f32 * cell_width + 4.0,
+            y,
+            9.0,
+            Font::Bold,
+            weekday,
+        );
+    }
+    y -= 6.0;
+
+    let mut row = 0;
+    let mut date = month.from;
+    while date <= month.to {
+        let column = date.weekday().num_days_from_monday() as usize;
+        let x = MARGIN + column as f32 * cell_width;
+        let cell_y = y - (row + 1) as f32 * CELL_HEIGHT;
+
+        let shade = match period.contains(&date) {
+            true => None,
+            false => Some(0.9),
+        };
+        page.rect(x, cell_y, cell_width, CELL_HEIGHT, shade);
+        page.text(
+            x + 4.0,
+            cell_y + CELL_HEIGHT - 12.0,
+            8.0,
+            Font::Regular,
+            &date.day().to_string(),
+        );
+
+        if let Some(day) = sheet.days.get(&date).filter(|_| period.contains(&date)) {
+            let hours = format!("{}h", day.hours);
+            page.text(
+                x + (cell_width - text_width(&hours, 14.0)) / 2.0,
+                cell_y + 16.0,
+                14.0,
+                Font::Bold,
+                &hours,
+            );
+            if let Some(leave) = day.leave {
+                page.text(
+                    x + (cell_width - text_width(leave.label(), 7.0)) / 2.0,
+                    cell_y + 5.0,
+                    7.0,
+                    Font::Regular,
+                    leave.label(),
+                );
+            }
+        }
+
+        if column == 6 {
+            row += 1;
+        }
+        date = date.succ_opt().unwrap();
+    }
+
+    let rows = if month.to.weekday().num_days_from_monday() == 6 {
+        row
+    } else {
+        row + 1
+    };
+    let bottom = y - rows as f32 * CELL_HEIGHT - 24.0;
+
+    let total = format!(
+        "Total for {}: {} hours",
+        first.format("%B"),
+        sheet.total_hours()
+    );
+    page.text(
+        PAGE_WIDTH - MARGIN - text_width(&total, 10.0),
+        bottom,
+        10.0,
+        Font::Regular,
+        &total,
+    );
+
+    bottom
+}
+
+// What was done each day, for as many days as fit above the totals
+fn draw_notes(page: &mut Page, sheet: &MonthSheet, top: f32) {
+    let notes: Vec<(&NaiveDate, String)> = sheet
+        .days
+        .iter()
+        .filter_map(|(date, day)| day.remarks().map(|remarks| (date, remarks)))
+        .collect();
+    if notes.is_empty() {
+        return;
+    }
+
+    let bottom = MARGIN + 80.0;
+    let max_chars = ((PAGE_WIDTH - MARGIN * 2.0 - 60.0) / (8.0 * 0.52)) as usize;
+    let mut y = top - 24.0;
+    page.text(MARGIN, y, 10.0, Font::Bold, "Notes");
+
+    for (index, (date, note)) in notes.iter().enumerate() {
+        y -= 12.0;
+        if y < bottom {
+            let more = format!("and {} more days", notes.len() - index);
+            page.text(MARGIN + 60.0, y, 8.0, Font::Regular, &more);
+            break;
+        }
+
+        let note: String = match note.chars().count() > max_chars {
+            true => note.chars().take(max_chars - 1).collect::<String>() + "...",
+            false => note.to_string(),
+        };
+        page.text(
+            MARGIN,
+            y,
+            8.0,
+            Font::Bold,
+            &date.format("%a %-d %b").to_string(),
+        );
+        page.text(MARGIN + 60.0, y, 8.0, Font::Regular, &note);
+    }
+}
+
+// The hours per ticket as a two column table, returning where it ends
+fn draw_tickets(page: &mut Page, tickets: &[(String, Hours)], top: f32) -> f32 {
+    let mut y = top;
+    page.text(MARGIN, y, 14.0, Font::Bold, "Hours by ticket");
+    y -= 22.0;
+
+    let right = MARGIN + 250.0;
+    for (ticket, hours) in tickets {
+        if y < MARGIN + 80.0 {
+            page.text(MARGIN, y, 10.0, Font::Regular, "...");
+            break;
+        }
+        let hours = hours.to_string();
+        page.text(MARGIN, y, 10.0, Font::Regular, ticket);
+        page.text(
+            right - text_width(&hours, 10.0),
+            y,
+            10.0,
+            Font::Regular,
+            &hours,
+        );
+        page.line(MARGIN, y - 4.0, right, y - 4.0);
+        y -= 16.0;
+    }
+    y - 20.0
+}
+
+// The hours and days for each category of time as a three column table
+fn draw_categories(page: &mut Page, categories: &[(String, Hours, usize)], top: f32) -> f32 {
+    let mut y = top;
+    page.text(MARGIN, y, 14.0, Font::Bold, "Time by category");
+    y -= 22.0;
+
+    let hours_right = MARGIN + 250.0;
+    let days_right = MARGIN + 330.0;
+    page.text(
+        hours_right - text_width("Hours", 9.0),
+        y,
+        9.0,
+        Font::Bold,
+        "Hours",
+    );
+    page.text(
+        days_right - text_width("Days", 9.0),
+        y,
+        9.0,
+        Font::Bold,
+        "Days",
+    );
+    y -= 16.0;
+    for (category, hours, days) in categories {
+        let (hours, days) = (hours.to_string(), days.to_string());
+        page.text(MARGIN, y, 10.0, Font::Regular, category);
+        page.text(
+            hours_right - text_width(&hours, 10.0),
+            y,
+            10.0,
+            Font::Regular,
+            &hours,
+        );
+        page.text(
+            days_right - text_width(&days, 10.0),
+            y,
+            10.0,
+            Font::Regular,
+            &days,
+        );
+        page.line(MARGIN, y - 4.0, days_right, y - 4.0);
+        y -= 16.0;
+    }
+    y - 20.0
+}
+
+fn draw_totals(page: &mut Page, days: usize, total: Hours, money: Option<&Totals>) {
+    let y = MARGIN + 40.0;
+    page.line(MARGIN, y + 24.0, PAGE_WIDTH - MARGIN, y + 24.0);
+    page.text(MARGIN, y, 12.0, Font::Bold, "Days worked");
+    page.text(MARGIN + 120.0, y, 12.0, Font::Regular, &days.to_string());
+    page.text(MARGIN, y - 18.0, 12.0, Font::Bold, "Total hours");
+    page.text(
+        MARGIN + 120.0,
+        y - 18.0,
+        12.0,
+        Font::Regular,
+        &total.to_string(),
+    );
+
+    // the money owed in a column on the right, the total in bold
+    let lines = money.map(|money| money.lines()).unwrap_or_default();
+    let mut money_y = y;
+    for (index, (label, amount)) in lines.iter().enumerate() {
+        let font = match index == lines.len() - 1 {
+            true => Font::Bold,
+            false => Font::Regular,
+        };
+        page.text(PAGE_WIDTH / 2.0, money_y, 12.0, Font::Bold, label);
+        let amount_x = PAGE_WIDTH - MARGIN - text_width(amount, 12.0);
+        page.text(amount_x, money_y, 12.0, font, amount);
+        money_y -= 16.0;
+    }
+}
+
+// One page per month in the period and one for the time per category and
+// ticket, with the totals on the last page
+pub fn render(repo: &Repo, period: &Period) -> Vec<u8> {
+    let mut months: Vec<MonthSheet> = vec![];
+    let mut date = period.from;
+    while date <= period.to {
+        let sheet = repo
+            .timesheet
+            .months
+            .iter()
+            .find(|sheet| sheet.year == date.year() && sheet.month == date.month())
+            .cloned()
+            .unwrap_or_else(|| MonthSheet::new(date.year(), date.month()));
+        months.push(sheet);
+        date = Period::current_month(date).to.succ_opt().unwrap();
+    }
+
+    let mut days = 0;
+    let mut total = Hours::default();
+    let mut pages = vec![];
+    for sheet in months.iter_mut() {
+        sheet.days.retain(|date, _| period.contains(date));
+        days += sheet.days_worked();
+        total += sheet.total_hours();
+
+        let mut page = Page::new();
+        let top = draw_header(&mut page, repo, period);
+        let bottom = draw_month(&mut page, sheet, period, top);
+        draw_notes(&mut page, sheet, bottom);
+        pages.push(page);
+    }
+
+    let categories = category_summary(repo, period);
+    let tickets = ticket_summary(repo, period);
+    if !categories.is_empty() || !tickets.is_empty() {
+        let mut page = Page::new();
+        let mut top = draw_header(&mut page, repo, period);
+        if !categories.is_empty() {
+            top = draw_categories(&mut page, &categories, top);
+        }
+        if !tickets.is_empty() {
+            draw_tickets(&mut page, &tickets, top);
+        }
+        pages.push(page);
+    }
+
+    if let Some(page) = pages.last_mut() {
+        draw_totals(page, days, total, invoice_totals(repo, period).as_ref());
+    }
+
+    write_document(&pages)
+}
+
+// The lines of a party's name, contact and address that are set
+fn party_lines(party: &Party) -> Vec<&str> {
+    [party.name.as_str(), party.contact.as_str()]
+        .iter()
+        .copied()
+        .chain(party.address.lines().map(|line| line.trim_end_matches(',')))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+// The invoice number, parties and dates, returning the height that is left
+fn draw_invoice_header(page: &mut Page, invoice: &Invoice) -> f32 {
+    let mut y = PAGE_HEIGHT - MARGIN - 20.0;
+    page.text(MARGIN, y, 20.0, Font::Bold, "Invoice");
+    page.text(
+        PAGE_WIDTH - MARGIN - text_width(&invoice.reference, 12.0),
+        y,
+        12.0,
+        Font::Bold,
+        &invoice.reference,
+    );
+
+    y -= 30.0;
+    let mut bottom = y;
+    for (x, heading, party) in [
+        (MARGIN, "From", &invoice.contractor),
+        (PAGE_WIDTH / 2.0, "Bill to", &invoice.client),
+    ] {
+        let mut party_y = y;
+        page.text(x, party_y, 10.0, Font::Bold, heading);
+        for line in party_lines(party) {
+            party_y -= 13.0;
+            page.text(x, party_y, 10.0, Font::Regular, line);
+        }
+        bottom = bottom.min(party_y);
+    }
+
+    y = bottom - 24.0;
+    let details = [
+        (
+            "Issue date",
+            invoice.issue_date.format("%-d %B %Y").to_string(),
+        ),
+        ("Due date", invoice.due_date.format("%-d %B %Y").to_string()),
+        ("Period", invoice.period().to_string()),
+        ("Terms", invoice.payment_terms.clone()),
+    ];
+    for (label, value) in details.iter() {
+        page.text(MARGIN, y, 10.0, Font::Bold, label);
+        page.text(MARGIN + 80.0, y, 10.0, Font::Regular, value);
+        y -= 13.0;
+    }
+
+    y - 20.0
+}
+
+fn draw_line_item_headings(page: &mut Page, y: f32) {
+    page.text(MARGIN, y, 9.0, Font::Bold, "Date");
+    page.text(MARGIN + 70.0, y, 9.0, Font::Bold, "Description");
+    let hours_x = PAGE_WIDTH - MARGIN - 110.0;
+    page.text(
+        hours_x - text_width("Hours", 9.0),
+        y,
+        9.0,
+        Font::Bold,
+        "Hours",
+    );
+    page.text(
+        PAGE_WIDTH - MARGIN - text_width("Amount", 9.0),
+        y,
+        9.0,
+        Font::Bold,
+        "Amount",
+    );
+    page.line(MARGIN, y - 5.0, PAGE_WIDTH - MARGIN, y - 5.0);
+}
+
+// The totals and where to pay, below the last line item
+fn draw_invoice_totals(page: &mut Page, invoice: &Invoice, top: f32) {
+    let mut y = top;
+    let lines = invoice.totals.lines();
+    for (index, (label, amount)) in lines.iter().enumerate() {
+        let font = match index == lines.len() - 1 {
+            true => Font::Bold,
+            false => Font::Regular,
+        };
+        page.text(PAGE_WIDTH / 2.0, y, 11.0, Font::Bold, label);
+        let amount_x = PAGE_WIDTH - MARGIN - text_width(amount, 11.0);
+        page.text(amount_x, y, 11.0, font, amount);
+        y -= 15.0;
+    }
+
+    let bank_details = invoice
+        .bank_details
+        .as_ref()
+        .map(|details| details.lines())
+        .unwrap_or_default();
+    if bank_details.is_empty() {
+        return;
+    }
+
+    y -= 15.0;
+    page.text(MARGIN, y, 10.0, Font::Bold, "Payment details");
+    for (label, value) in bank_details {
+        y -= 13.0;
+        page.text(MARGIN, y, 9.0, Font::Bold, label);
+        page.text(MARGIN + 100.0, y, 9.0, Font::Regular, value);
+    }
+}
+
+// An issued invoice, with a line per day worked. Only the invoice itself is used
+// so rendering it again gives the same document
+pub fn render_invoice(invoice: &Invoice) -> Vec<u8> {
+    let row_height = 14.0;
+    let totals_height = 80.0 + 13.0 * 7.0;
+    let max_chars = ((PAGE_WIDTH - MARGIN * 2.0 - 200.0) / (8.0 * 0.52)) as usize;
+
+    let mut pages = vec![];
+    let mut page = Page::new();
+    let mut y = draw_invoice_header(&mut page, invoice);
+    draw_line_item_headings(&mut page, y);
+    y -= 20.0;
+
+    for line in invoice.lines.iter() {
+        if y < MARGIN {
+            pages.push(page);
+            page = Page::new();
+            y = PAGE_HEIGHT - MARGIN - 20.0;
+            draw_line_item_headings(&mut page, y);
+            y -= 20.0;
+        }
+
+        let description: String = match line.description.chars().count() > max_chars {
+            true => {
+                line.description
+                    .chars()
+                    .take(max_chars - 1)
+                    .collect::<String>()
+                    + "..."
+            }
+            false => line.description.clone(),
+        };
+        let hours = line.hours.to_string();
+        let amount = line.amount.to_string();
+        page.text(
+            MARGIN,
+            y,
+            8.0,
+            Font::Regular,
+            &line.date.format("%a %-d %b").to_string(),
+        );
+        page.text(MARGIN + 70.0, y, 8.0, Font::Regular, &description);
+        let hours_x = PAGE_WIDTH - MARGIN - 110.0;
+        page.text(
+            hours_x - text_width(&hours, 8.0),
+            y,
+            8.0,
+            Font::Regular,
+            &hours,
+        );
+        page.text(
+            PAGE_WIDTH - MARGIN - text_width(&amount, 8.0),
+            y,
+            8.0,
+            Font::Regular,
+            &amount,
+        );
+        y -= row_height;
+    }
+
+    if y - totals_height < MARGIN {
+        pages.push(page);
+        page = Page::new();
+        y = PAGE_HEIGHT - MARGIN;
+    }
+    page.line(MARGIN, y + 4.0, PAGE_WIDTH - MARGIN, y + 4.0);
+    draw_invoice_totals(&mut page, invoice, y - 14.0);
+    pages.push(page);
+
+    write_document(&pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_escapes_text_for_the_content_stream() {
+        assert_eq!(
+            escape("Smith (Ltd) \\ Café €1,200.00"),
+            "Smith \\(Ltd\\) \\\\ Caf\\351 \\2001,200.00"
+        );
+        assert_eq!(
+            escape("“Quoted” – done… ✓"),
+            "\\223Quoted\\224 \\226 done\\205 ?"
+        );
+    }
+
+    #[test]
+    fn it_writes_a_valid_cross_reference_table() {
+        let mut page = Page::new();
+        page.text(MARGIN, MARGIN, 12.0, Font::Regular, "Hello");
+        let pdf = String::from_utf8(write_document(&[page])).unwrap();
+
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+
+        // every offset in the table points at the start of its object
+        let xref = pdf.find("\nxref\n").unwrap() + 1;
+        let offsets: Vec<usize> = pdf[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|line| line.ends_with(" n "))
+            .map(|line| line[..10].parse().unwrap())
+            .collect();
+        assert_eq!(offsets.len(), 6);
+        for (index, offset) in offsets.iter().enumerate() {
+            assert!(pdf[*offset..].starts_with(&format!("{} 0 obj", index + 1)));
+        }
+    }
+}
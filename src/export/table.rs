@@ -0,0 +1,259 @@
+use super::{category_summary, invoice_totals, ticket_summary};
+use crate::period::Period;
+use crate::repo::Repo;
+use crate::timesheet::{Hours, MonthSheet};
+use chrono::{Datelike, NaiveDate};
+
+const HEADER: [&str; 9] = [
+    "Week", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun", "Total",
+];
+
+// A month as rows of cells, one row per week with the week's total on the
+// right and a final row of totals per weekday
+fn month_rows(sheet: &MonthSheet, period: &Period) -> Vec<Vec<String>> {
+    let first = NaiveDate::from_ymd_opt(sheet.year, sheet.month, 1).unwrap();
+    let month = Period::current_month(first);
+
+    let mut rows = vec![];
+    let mut week: Vec<String> = vec![String::new(); 9];
+    let mut week_total = Hours::default();
+    let mut weekday_totals = [Hours::default(); 7];
+    let mut total = Hours::default();
+
+    let mut date = month.from;
+    while date <= month.to {
+        let column = date.weekday().num_days_from_monday() as usize;
+        if week[0].is_empty() {
+            week[0] = date.iso_week().week().to_string();
+        }
+
+        week[column + 1] = match sheet.days.get(&date).filter(|_| period.contains(&date)) {
+            Some(day) => {
+                week_total += day.hours;
+                weekday_totals[column] += day.hours;
+                total += day.hours;
+                match day.leave {
+                    Some(leave) if day.hours == Hours::default() => {
+                        format!("{}: {}", date.day(), leave.label())
+                    }
+                    _ => format!("{}: {}", date.day(), day.hours),
+                }
+            }
+            None if period.contains(&date) => date.day().to_string(),
+            None => String::new(),
+        };
+
+        if column == 6 || date == month.to {
+            week[8] = week_total.to_string();
+            rows.push(week);
+            week = vec![String::new(); 9];
+            week_total = Hours::default();
+        }
+        date = date.succ_opt().unwrap();
+    }
+
+    let mut totals = vec!["Total".to_string()];
+    totals.extend(weekday_totals.iter().map(|hours| hours.to_string()));
+    totals.push(total.to_string());
+    rows.push(totals);
+
+    rows
+}
+
+// Months in the period that have at least one day worked
+fn months<'a>(repo: &'a Repo, period: &'a Period) -> impl Iterator<Item = &'a MonthSheet> {
+    repo.timesheet
+        .months
+        .iter()
+        .filter(move |sheet| sheet.days.keys().any(|date| period.contains(date)))
+}
+
+// The days in the period that have a note, or aren't working days
+fn notes<'a>(sheet: &'a MonthSheet, period: &'a Period) -> Vec<(&'a NaiveDate, String)> {
+    sheet
+        .days
+        .iter()
+        .filter(|(date, _)| period.contains(date))
+        .filter_map(|(date, day)| day.remarks().map(|remarks| (date, remarks)))
+        .collect()
+}
+
+fn month_title(sheet: &MonthSheet) -> String {
+    NaiveDate::from_ymd_opt(sheet.year, sheet.month, 1)
+        .unwrap()
+        .format("%B %Y")
+        .to_string()
+}
+
+// Plain text for the terminal, with the columns padded to line up
+pub fn render_text(repo: &Repo, period: &Period) -> String {
+    let mut text = format!("Timesheet for {}: {}\n", repo.namespace, period);
+
+    for sheet in months(repo, period) {
+        let rows = month_rows(sheet, period);
+        let widths: Vec<usize> = (0..HEADER.len())
+            .map(|column| {
+                rows.iter()
+                    .map(|row| row[column].len())
+                    .chain([HEADER[column].len()])
+                    .max()
+                    .unwrap()
+            })
+            .collect();
+
+        let line = |cells: Vec<&str>| -> String {
+            let cells: Vec<String> = cells
+                .iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+                .collect();
+            format!("| {} |\n", cells.join(" | "))
+        };
+        let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        let rule = format!("+-{}-+\n", rule.join("-+-"));
+
+        text += &format!("\n{}\n", month_title(sheet));
+        text += &rule;
+        text += &line(HEADER.to_vec());
+        text += &rule;
+        for (index, row) in rows.iter().enumerate() {
+            if index == rows.len() - 1 {
+                text += &rule;
+            }
+            text += &line(row.iter().map(|cell| cell.as_str()).collect());
+        }
+        text += &rule;
+
+        for (date, note) in notes(sheet, period) {
+            text += &format!("{:>10}  {}\n", date.format("%a %-d %b"), note);
+        }
+    }
+
+    let categories = category_summary(repo, period);
+    if !categories.is_empty() {
+        let width = categories
+            .iter()
+            .map(|(category, _, _)| category.len())
+            .max()
+            .unwrap();
+        text += "\nTime by category\n";
+        for (category, hours, days) in categories {
+            text += &format!(
+                "{:<width$}  {:>6} hours  {:>3} {}\n",
+                category,
+                hours,
+                days,
+                match days {
+                    1 => "day",
+                    _ => "days",
+                },
+                width = width
+            );
+        }
+    }
+
+    let tickets = ticket_summary(repo, period);
+    if !tickets.is_empty() {
+        let width = tickets
+            .iter()
+            .map(|(ticket, _)| ticket.len())
+            .max()
+            .unwrap();
+        text += "\nHours by ticket\n";
+        for (ticket, hours) in tickets {
+            text += &format!("{:<width$}  {:>6}\n", ticket, hours, width = width);
+        }
+    }
+
+    text += &format!("\nTotal: {} hours\n", repo.timesheet.total_hours());
+    if let Some(totals) = invoice_totals(repo, period) {
+        let lines = totals.lines();
+        let width = lines.iter().map(|(label, _)| label.len()).max().unwrap();
+        for (label, amount) in lines {
+            text += &format!("{:<width$}  {:>14}\n", label, amount, width = width);
+        }
+    }
+    text
+}
+
+// The same table as Markdown, for pasting into pull requests and wikis
+pub fn render_markdown(repo: &Repo, period: &Period) -> String {
+    let mut markdown = format!("# Timesheet for {}: {}\n", repo.namespace, period);
+
+    for sheet in months(repo, period) {
+        markdown += &format!("\n## {}\n\n", month_title(sheet));
+        markdown += &format!("| {} |\n", HEADER.join(" | "));
+        markdown += &format!("|{}\n", "---:|".repeat(HEADER.len()));
+
+        let rows = month_rows(sheet, period);
+        for (index, row) in rows.iter().enumerate() {
+            let cells: Vec<String> = match index == rows.len() - 1 {
+                true => row.iter().map(|cell| format!("**{}**", cell)).collect(),
+                false => row.iter().map(|cell| cell.replace('|', "\\|")).collect(),
+            };
+            markdown += &format!("| {} |\n", cells.join(" | "));
+        }
+
+        let notes = notes(sheet, period);
+        if !notes.is_empty() {
+            markdown += "\n";
+        }
+        for (date, note) in notes {
+            markdown += &format!("- **{}**: {}\n", date.format("%a %-d %b"), note);
+        }
+    }
+
+    let categories = category_summary(repo, period);
+    if !categories.is_empty() {
+        markdown += "\n## Time by category\n\n| Category | Hours | Days |\n|---|---:|---:|\n";
+    }
+    for (category, hours, days) in categories {
+        markdown += &format!("| {} | {} | {} |\n", category, hours, days);
+    }
+
+    let tickets = ticket_summary(repo, period);
+    if !tickets.is_empty() {
+        markdown += "\n## Hours by ticket\n\n| Ticket | Hours |\n|---|---:|\n";
+    }
+    for (ticket, hours) in tickets {
+        markdown += &format!("| {} | {} |\n", ticket.replace('|', "\\|"), hours);
+    }
+
+    markdown += &format!("\n**Total: {} hours**\n", repo.timesheet.total_hours());
+    if let Some(totals) = invoice_totals(repo, period) {
+        markdown += "\n| | Amount |\n|---|---:|\n";
+        for (label, amount) in totals.lines() {
+            markdown += &format!("| {} | {} |\n", label, amount);
+        }
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timesheet::{DayEntry, Timesheet};
+
+    #[test]
+    fn it_lays_out_a_month_as_weeks() {
+        let mut timesheet = Timesheet::new();
+        let september = |day| NaiveDate::from_ymd_opt(2021, 9, day).unwrap();
+        timesheet.insert_day(september(1), DayEntry::new(Hours::from_hours(8.0)));
+        timesheet.insert_day(september(6), DayEntry::new(Hours::from_hours(7.5)));
+        timesheet.insert_day(september(7), DayEntry::new(Hours::from_hours(4.0)));
+
+        let rows = month_rows(&timesheet.months[0], &Period::month(2021, 9).unwrap());
+
+        // 1 September 2021 was a Wednesday
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[0], ["35", "", "", "1: 8", "2", "3", "4", "5", "8"]);
+        assert_eq!(rows[1][1..3], ["6: 7.5", "7: 4"]);
+        assert_eq!(rows[1][8], "11.5");
+        assert_eq!(rows[4][..4], ["39", "27", "28", "29"]);
+        assert_eq!(rows[4][4..], ["30", "", "", "", "0"]);
+        assert_eq!(
+            rows[5],
+            ["Total", "7.5", "4", "8", "0", "0", "0", "0", "19.5"]
+        );
+    }
+}
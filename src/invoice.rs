@@ -0,0 +1,386 @@
+use crate::period::Period;
+use crate::rates::{Money, Totals};
+use crate::repo::Repo;
+use crate::timesheet::{DayEntry, Hours};
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// Where payment should be made, printed at the bottom of every invoice
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BankDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bank: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort_code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_number: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iban: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bic: Option<String>,
+}
+
+impl BankDetails {
+    // The details that are set, as label and value
+    pub fn lines(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("Account name", &self.account_name),
+            ("Bank", &self.bank),
+            ("Sort code", &self.sort_code),
+            ("Account number", &self.account_number),
+            ("IBAN", &self.iban),
+            ("BIC", &self.bic),
+        ]
+        .iter()
+        .filter_map(|(label, value)| value.as_deref().map(|value| (*label, value)))
+        .collect()
+    }
+}
+
+// The contractor's invoicing details, shared by every project in the config file
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceSettings {
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+    #[serde(default = "default_payment_terms_days")]
+    pub payment_terms_days: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contractor_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bank_details: Option<BankDetails>,
+}
+
+fn default_prefix() -> String {
+    "INV-".to_string()
+}
+
+fn default_payment_terms_days() -> u32 {
+    30
+}
+
+impl Default for InvoiceSettings {
+    fn default() -> InvoiceSettings {
+        InvoiceSettings {
+            prefix: default_prefix(),
+            payment_terms_days: default_payment_terms_days(),
+            contractor_address: None,
+            bank_details: None,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Party {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub contact: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub address: String,
+}
+
+// A day from the timesheet
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceLine {
+    pub date: NaiveDate,
+    pub description: String,
+    pub hours: Hours,
+    pub amount: Money,
+}
+
+// An issued invoice. Everything needed to render it is kept, so that it can be
+// rendered again exactly as it was issued
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Invoice {
+    pub number: u32,
+    pub reference: String,
+    pub project: String,
+    pub issue_date: NaiveDate,
+    pub due_date: NaiveDate,
+    pub payment_terms: String,
+    pub period_from: NaiveDate,
+    pub period_to: NaiveDate,
+    pub contractor: Party,
+    pub client: Party,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bank_details: Option<BankDetails>,
+    pub lines: Vec<InvoiceLine>,
+    pub totals: Totals,
+}
+
+impl Invoice {
+    pub fn period(&self) -> Period {
+        Period {
+            from: self.period_from,
+            to: self.period_to,
+        }
+    }
+}
+
+// Every invoice issued, in the order they were issued. The next invoice number
+// always follows the last one, so the sequence has no gaps
+#[derive(PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Ledger {
+    pub invoices: Vec<Invoice>,
+}
+
+impl Ledger {
+    pub fn read(path: &Path) -> Result<Ledger, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(Ledger::default());
+        }
+
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    // Written to a temporary file first, so that a failed write can't lose
+    // the invoices that were already issued
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, serde_json::to_string_pretty(self)?)?;
+        fs::rename(temporary, path)?;
+        Ok(())
+    }
+
+    pub fn next_number(&self) -> u32 {
+        self.invoices.last().map_or(1, |invoice| invoice.number + 1)
+    }
+
+    pub fn find(&self, reference: &str) -> Option<&Invoice> {
+        self.invoices
+            .iter()
+            .find(|invoice| invoice.reference.eq_ignore_ascii_case(reference))
+    }
+
+    // Create the next invoice for the project's timesheet and add it to the ledger.
+    // A day can only be invoiced once for each project, so periods can't overlap
+    pub fn issue(
+        &mut self,
+        repo: &Repo,
+        period: &Period,
+        settings: &InvoiceSettings,
+        issue_date: NaiveDate,
+    ) -> Result<&Invoice, Box<dyn Error>> {
+        let rates = repo.rates.as_ref().ok_or(format!(
+            "No rates set for '{}', add them to the config file before invoicing",
+            repo.namespace
+        ))?;
+
+        if let Some(existing) = self.invoices.iter().find(|invoice| {
+            invoice.project == repo.namespace
+                && invoice.period_from <= period.to
+                && period.from <= invoice.period_to
+        }) {
+            return Err(format!(
+                "{} has already been invoiced for {} as {}, which overlaps {}",
+                repo.namespace,
+                existing.period(),
+                existing.reference,
+                period
+            )
+            .into());
+        }
+
+        if let Some((date, day_off)) = repo
+            .timesheet
+            .months
+            .iter()
+            .flat_map(|sheet| sheet.days.iter())
+            .filter(|(date, _)| period.contains(date))
+            .find_map(|(date, day)| {
+                day.day_off
+                    .as_ref()
+                    .filter(|day_off| day_off.flagged)
+                    .map(|day_off| (date, day_off))
+            })
+        {
+            return Err(format!(
+                "{} ({}) has commits on a day off that haven't been reviewed, \
+                 set its hours or remove it with 'timesheet-gen edit' before invoicing",
+                date.format("%a %-d %b %Y"),
+                day_off.reason
+            )
+            .into());
+        }
+
+        let days: Vec<(&NaiveDate, &DayEntry)> = repo
+            .timesheet
+            .months
+            .iter()
+            .flat_map(|sheet| sheet.days.iter())
+            .filter(|(date, day)| period.contains(date) && day.hours > Hours::default())
+            .collect();
+        let lines: Vec<InvoiceLine> = days
+            .iter()
+            .map(|(date, day)| {
+                let (hours, amount) = rates.charge(day);
+                InvoiceLine {
+                    date: **date,
+                    description: day
+                        .remarks()
+                        .unwrap_or_else(|| format!("Work on {}", repo.namespace)),
+                    hours,
+                    amount,
+                }
+            })
+            .collect();
+        if lines.is_empty() {
+            return Err(format!("No days worked on {} in {}", repo.namespace, period).into());
+        }
+
+        let number = self.next_number();
+        let invoice = Invoice {
+            number,
+            reference: format!("{}{:04}", settings.prefix, number),
+            project: repo.namespace.clone(),
+            issue_date,
+            due_date: issue_date + Duration::days(settings.payment_terms_days as i64),
+            payment_terms: format!("Payment due within {} days", settings.payment_terms_days),
+            period_from: period.from,
+            period_to: period.to,
+            contractor: Party {
+                name: repo.name.clone(),
+                contact: repo.email.clone(),
+                address: settings.contractor_address.clone().unwrap_or_default(),
+            },
+            client: Party {
+                name: repo.client_name.clone(),
+                contact: repo.contact_person.clone(),
+                address: repo.address.clone(),
+            },
+            bank_details: settings.bank_details.clone(),
+            totals: rates.totals(days.iter().map(|(_, day)| *day)),
+            lines,
+        };
+
+        self.invoices.push(invoice);
+        Ok(self.invoices.last().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rates::{Rate, RateConfig};
+    use crate::timesheet::{DayOff, DayOverride, Timesheet};
+
+    #[test]
+    fn it_issues_invoices_in_sequence_once_per_period() {
+        let september = |day| NaiveDate::from_ymd_opt(2021, 9, day).unwrap();
+        let mut timesheet = Timesheet::new();
+        timesheet.insert_day(september(6), DayEntry::new(Hours::from_hours(7.5)));
+        timesheet.insert_day(september(30), DayEntry::new(Hours::from_hours(8.0)));
+        let mut repo = Repo {
+            namespace: "acme".to_string(),
+            name: "Tom Jones".to_string(),
+            client_name: "Acme Corp".to_string(),
+            timesheet,
+            ..Default::default()
+        };
+
+        let mut ledger = Ledger::default();
+        let period = Period::month(2021, 9).unwrap();
+        let settings = InvoiceSettings::default();
+        assert!(ledger
+            .issue(&repo, &period, &settings, september(30))
+            .is_err());
+
+        repo.rates = Some(RateConfig {
+            rate: Rate::Hourly { amount: 50.0 },
+            currency: "GBP".to_string(),
+            tax_percent: Some(20.0),
+            rounding: None,
+        });
+        let invoice = ledger
+            .issue(&repo, &period, &settings, september(30))
+            .unwrap();
+        assert_eq!(invoice.reference, "INV-0001");
+        assert_eq!(
+            invoice.due_date,
+            NaiveDate::from_ymd_opt(2021, 10, 30).unwrap()
+        );
+        assert_eq!(invoice.lines.len(), 2);
+        assert_eq!(invoice.lines[0].amount, Money(37500));
+        assert_eq!(invoice.totals.total, Money(93000));
+
+        // neither the same period nor one overlapping it can be invoiced again
+        assert!(ledger
+            .issue(&repo, &period, &settings, september(30))
+            .is_err());
+        let overlapping = Period::new(september(15), september(30)).unwrap();
+        assert!(ledger
+            .issue(&repo, &overlapping, &settings, september(30))
+            .is_err());
+        let straddling =
+            Period::new(september(30), NaiveDate::from_ymd_opt(2021, 10, 5).unwrap()).unwrap();
+        assert!(ledger
+            .issue(&repo, &straddling, &settings, september(30))
+            .is_err());
+        assert_eq!(ledger.invoices.len(), 1);
+
+        // but another project can be, and its number follows on
+        repo.namespace = "other".to_string();
+        let invoice = ledger
+            .issue(&repo, &overlapping, &settings, september(30))
+            .unwrap();
+        assert_eq!(invoice.number, 2);
+        assert_eq!(invoice.lines.len(), 1);
+        assert!(ledger.find("inv-0002").is_some());
+
+        let json = serde_json::to_string(&ledger).unwrap();
+        assert_eq!(serde_json::from_str::<Ledger>(&json).unwrap(), ledger);
+    }
+
+    #[test]
+    fn it_refuses_days_off_that_havent_been_reviewed() {
+        let sunday = NaiveDate::from_ymd_opt(2021, 9, 5).unwrap();
+        let mut timesheet = Timesheet::new();
+        timesheet.insert_day(
+            sunday,
+            DayEntry {
+                hours: Hours::from_hours(3.0),
+                day_off: Some(DayOff {
+                    reason: "Sunday".to_string(),
+                    overtime: None,
+                    flagged: true,
+                }),
+                ..Default::default()
+            },
+        );
+        let mut repo = Repo {
+            namespace: "acme".to_string(),
+            timesheet,
+            rates: Some(RateConfig {
+                rate: Rate::Hourly { amount: 50.0 },
+                currency: "GBP".to_string(),
+                tax_percent: None,
+                rounding: None,
+            }),
+            ..Default::default()
+        };
+
+        let mut ledger = Ledger::default();
+        let period = Period::month(2021, 9).unwrap();
+        let settings = InvoiceSettings::default();
+        let error = ledger
+            .issue(&repo, &period, &settings, sunday)
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("Sun 5 Sep 2021 (Sunday) has commits on a day off"));
+
+        // once reviewed, the client sees why the day is on the invoice but not the flag
+        repo.timesheet.apply_override(
+            sunday,
+            &DayOverride {
+                hours: Some(Hours::from_hours(3.0)),
+                ..Default::default()
+            },
+        );
+        let invoice = ledger.issue(&repo, &period, &settings, sunday).unwrap();
+        assert_eq!(invoice.lines[0].description, "[Sunday]");
+    }
+}
@@ -1,16 +1,29 @@
 extern crate git2;
-use std::env;
+use clap::Parser;
 use std::process;
 
+mod calendar;
+mod cli;
+mod commits;
 mod config;
 mod db;
+mod editor;
+mod estimate;
+mod export;
+mod invoice;
 mod mock_repo_dep;
+mod period;
+mod projects;
+mod rates;
 mod repo;
+mod store;
+mod tickets;
+mod timesheet;
 mod utils;
 
 fn main() {
     // Construct Config struct with repo path and commands
-    let config: config::Config = config::Config::new(env::args()).unwrap_or_else(|err| {
+    let config: config::Config = config::Config::new(cli::Cli::parse()).unwrap_or_else(|err| {
         eprintln!("Problem parsing arguments: {}", err);
         process::exit(1);
     });
@@ -0,0 +1,119 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use std::fmt;
+
+// An inclusive range of dates that a timesheet is generated for
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Period {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl Period {
+    pub fn new(from: NaiveDate, to: NaiveDate) -> Result<Period, String> {
+        if from > to {
+            return Err(format!("'{}' is after '{}'", from, to));
+        }
+
+        Ok(Period { from, to })
+    }
+
+    pub fn month(year: i32, month: u32) -> Result<Period, String> {
+        let from = NaiveDate::from_ymd_opt(year, month, 1)
+            .ok_or_else(|| format!("'{}-{}' is not a valid month", year, month))?;
+        let (next_year, next_month) = if month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, month + 1)
+        };
+        let to = NaiveDate::from_ymd_opt(next_year, next_month, 1).unwrap() - Duration::days(1);
+
+        Period::new(from, to)
+    }
+
+    pub fn current_month(today: NaiveDate) -> Period {
+        Period::month(today.year(), today.month()).unwrap()
+    }
+
+    pub fn previous_month(today: NaiveDate) -> Period {
+        let last_day_of_previous_month = today.with_day(1).unwrap() - Duration::days(1);
+        Period::current_month(last_day_of_previous_month)
+    }
+
+    // Parse a month in the form YYYY-MM e.g. 2021-09
+    pub fn parse_month(month: &str) -> Result<Period, String> {
+        let date = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+            .map_err(|_| format!("'{}' is not a valid month, expected YYYY-MM", month))?;
+        Period::month(date.year(), date.month())
+    }
+
+    // Parse a date in the form YYYY-MM-DD e.g. 2021-09-30
+    pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("'{}' is not a valid date, expected YYYY-MM-DD", date))
+    }
+
+    pub fn contains(&self, date: &NaiveDate) -> bool {
+        &self.from <= date && date <= &self.to
+    }
+
+    // Every date in the period, in order
+    pub fn days(&self) -> impl Iterator<Item = NaiveDate> {
+        let to = self.to;
+        self.from.iter_days().take_while(move |date| *date <= to)
+    }
+
+    fn is_whole_month(&self) -> bool {
+        Period::current_month(self.from) == *self
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_whole_month() {
+            write!(f, "{}", self.from.format("%B %Y"))
+        } else {
+            write!(
+                f,
+                "{} to {}",
+                self.from.format("%-d %B %Y"),
+                self.to.format("%-d %B %Y")
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn it_finds_the_previous_month_across_a_year_boundary() {
+        let period = Period::previous_month(date(2022, 1, 2));
+
+        assert_eq!(period.from, date(2021, 12, 1));
+        assert_eq!(period.to, date(2021, 12, 31));
+        assert_eq!(period.to_string(), "December 2021");
+    }
+
+    #[test]
+    fn it_parses_a_month() {
+        let period = Period::parse_month("2020-02").unwrap();
+
+        assert_eq!(period.to, date(2020, 2, 29));
+        assert!(period.contains(&date(2020, 2, 1)));
+        assert!(!period.contains(&date(2020, 3, 1)));
+        assert!(Period::parse_month("2020-13").is_err());
+    }
+
+    #[test]
+    fn it_describes_a_date_range() {
+        let period = Period::new(date(2021, 9, 6), date(2021, 10, 1)).unwrap();
+
+        assert_eq!(period.to_string(), "6 September 2021 to 1 October 2021");
+        assert!(Period::new(date(2021, 10, 1), date(2021, 9, 6)).is_err());
+    }
+}
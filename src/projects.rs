@@ -0,0 +1,178 @@
+use crate::invoice::InvoiceSettings;
+use crate::repo::Repo;
+use crate::store::StorageConfig;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::process;
+
+// The contents of the config file: every project (client) that timesheets are made
+// for, each one being a repo struct keyed by its namespace
+#[derive(PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Projects {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_project: Option<String>,
+    pub projects: Vec<Repo>,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub invoicing: InvoiceSettings,
+}
+
+impl Projects {
+    // Config files written before projects were introduced hold a single repo
+    // struct. The shape is told apart by its keys rather than by trying each in
+    // turn, so that a mistake in the file is reported where it was made
+    pub fn from_json(json: &str) -> Result<Projects, serde_json::Error> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        if value.get("projects").is_some() {
+            return serde_json::from_str(json);
+        }
+
+        let repo: Repo = serde_json::from_str(json)?;
+        Ok(Projects {
+            default_project: Some(repo.namespace.clone()),
+            projects: vec![repo],
+            ..Default::default()
+        })
+    }
+
+    pub fn read_config_file(config_path: &str) -> Result<Projects, Box<dyn Error>> {
+        let mut buffer = String::new();
+        File::open(config_path)?.read_to_string(&mut buffer)?;
+
+        Ok(Projects::from_json(&buffer)?)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.projects
+            .iter()
+            .map(|project| project.namespace.as_str())
+            .collect()
+    }
+
+    // The project with the given name, otherwise the default project,
+    // otherwise the only project in the config
+    fn position(&self, name: Option<&str>) -> Result<usize, String> {
+        let name = match (name, &self.default_project) {
+            (Some(name), _) => name,
+            (None, Some(default_project)) => default_project.as_str(),
+            (None, None) if self.projects.len() == 1 => return Ok(0),
+            (None, None) => {
+                return Err(format!(
+                    "No default project set, choose one of: {}",
+                    self.names().join(", ")
+                ))
+            }
+        };
+
+        self.projects
+            .iter()
+            .position(|project| project.namespace == name)
+            .ok_or_else(|| {
+                format!(
+                    "Project '{}' not found, choose one of: {}",
+                    name,
+                    self.names().join(", ")
+                )
+            })
+    }
+
+    pub fn find(&self, name: Option<&str>) -> Result<&Repo, String> {
+        let position = self.position(name)?;
+        Ok(&self.projects[position])
+    }
+
+    pub fn find_mut(&mut self, name: Option<&str>) -> Result<&mut Repo, String> {
+        let position = self.position(name)?;
+        Ok(&mut self.projects[position])
+    }
+
+    // Adds the project, replacing any project with the same name.
+    // The first project added becomes the default
+    pub fn upsert(&mut self, project: Repo) {
+        if self.default_project.is_none() {
+            self.default_project = Some(project.namespace.clone());
+        }
+
+        match self
+            .projects
+            .iter()
+            .position(|existing| existing.namespace == project.namespace)
+        {
+            Some(position) => self.projects[position] = project,
+            None => self.projects.push(project),
+        }
+    }
+
+    pub fn set_default_project(&mut self, name: &str) -> Result<(), String> {
+        let position = self.position(Some(name))?;
+        self.default_project = Some(self.projects[position].namespace.clone());
+        Ok(())
+    }
+
+    pub fn save(&self, config_path: &str) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string(&self)?;
+        let mut file = File::create(config_path)?;
+
+        file.write_all(json.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn write_config_file(&self, config_path: &str) -> Result<(), Box<dyn Error>> {
+        self.save(config_path)?;
+        println!(
+            "timesheet-gen initialised. Try 'timesheet-gen make' to create your first timesheet."
+        );
+        process::exit(exitcode::OK);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SINGLE_REPO_CONFIG: &str = r#"{"namespace":"timesheet","path":"/path/to/timesheet/.git/","name":"Tom Jones","email":"sex_bomb@gmail.com","client_name":"Acme","contact_person":"Wile E. Coyote","address":"","timesheet":{}}"#;
+
+    #[test]
+    fn it_reads_a_config_file_with_a_single_repo() {
+        let projects = Projects::from_json(SINGLE_REPO_CONFIG).unwrap();
+
+        assert_eq!(projects.names(), vec!["timesheet"]);
+        assert_eq!(projects.default_project, Some("timesheet".to_string()));
+        assert_eq!(projects.find(None).unwrap().client_name, "Acme");
+    }
+
+    #[test]
+    fn it_finds_projects_by_name_or_default() {
+        let mut projects = Projects::from_json(SINGLE_REPO_CONFIG).unwrap();
+        let mut second: Repo = serde_json::from_str(SINGLE_REPO_CONFIG).unwrap();
+        second.namespace = "acme".to_string();
+        projects.upsert(second);
+
+        assert_eq!(projects.find(None).unwrap().namespace, "timesheet");
+        assert_eq!(projects.find(Some("acme")).unwrap().namespace, "acme");
+        assert!(projects.find(Some("roadrunner")).is_err());
+
+        projects.set_default_project("acme").unwrap();
+        assert_eq!(projects.find(None).unwrap().namespace, "acme");
+    }
+
+    #[test]
+    fn it_reports_the_field_that_a_config_file_gets_wrong() {
+        let repo = SINGLE_REPO_CONFIG.replace(
+            r#""timesheet":{}"#,
+            r#""timesheet":{},"time":{"timezone":"Europe/Berln"}"#,
+        );
+        let config = format!(r#"{{"default_project":"timesheet","projects":[{}]}}"#, repo);
+
+        let error = Projects::from_json(&config).unwrap_err().to_string();
+        assert!(error.contains("Europe/Berln"), "{}", error);
+        assert!(error.contains("column"), "{}", error);
+        assert!(Projects::from_json(&repo)
+            .unwrap_err()
+            .to_string()
+            .contains("Europe/Berln"));
+    }
+}
@@ -0,0 +1,264 @@
+use crate::timesheet::{DayEntry, Hours};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// An amount of money in minor units e.g. pence, so that totals add up exactly
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Money(pub i64);
+
+impl Money {
+    fn from_major(amount: f64) -> Money {
+        Money((amount * 100.0).round() as i64)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        write!(
+            f,
+            "{}{}.{:02}",
+            sign,
+            self.0.abs() / 100,
+            self.0.abs() % 100
+        )
+    }
+}
+
+// What is charged, either by the hour or by the day e.g.
+// { "unit": "daily", "amount": 450, "half_day_hours": 4 }
+// A day rate charges every day worked as a whole day, however long it was, or
+// as half a day when it's no longer than the half day threshold
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "unit", rename_all = "snake_case")]
+pub enum Rate {
+    Hourly {
+        amount: f64,
+    },
+    Daily {
+        amount: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        half_day_hours: Option<f64>,
+    },
+}
+
+#[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+    Up,
+    Down,
+    Nearest,
+}
+
+// How each day's hours are rounded before they are charged e.g. up to the next
+// quarter hour with { "increment_minutes": 15, "mode": "up" }
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Rounding {
+    pub increment_minutes: u32,
+    pub mode: RoundingMode,
+}
+
+impl Rounding {
+    fn round(&self, hours: Hours) -> Hours {
+        let increment = self.increment_minutes.max(1);
+        let minutes = hours.as_minutes();
+        let rounded_down = minutes / increment * increment;
+
+        let minutes = match self.mode {
+            RoundingMode::Down => rounded_down,
+            RoundingMode::Up if minutes > rounded_down => rounded_down + increment,
+            RoundingMode::Nearest if (minutes - rounded_down) * 2 >= increment => {
+                rounded_down + increment
+            }
+            _ => rounded_down,
+        };
+        Hours::from_minutes(minutes)
+    }
+}
+
+// The rate charged for a project, kept in the config file alongside the client details
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct RateConfig {
+    #[serde(flatten)]
+    pub rate: Rate,
+    pub currency: String,
+    // VAT or sales tax as a percentage e.g. 20
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_percent: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rounding: Option<Rounding>,
+}
+
+// The money owed for a timesheet
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Totals {
+    pub currency: String,
+    // The hours charged for, after rounding
+    pub billable_hours: Hours,
+    pub subtotal: Money,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_percent: Option<f64>,
+    pub tax: Money,
+    pub total: Money,
+}
+
+impl RateConfig {
+    // The hours charged for a day after rounding, and what they cost
+    // including any overtime
+    pub fn charge(&self, day: &DayEntry) -> (Hours, Money) {
+        let billable = match &self.rounding {
+            Some(rounding) => rounding.round(day.hours),
+            None => day.hours,
+        };
+
+        let charged = match self.rate {
+            Rate::Hourly { amount } => billable.as_hours() * amount,
+            Rate::Daily { .. } if billable == Hours::default() => 0.0,
+            Rate::Daily {
+                amount,
+                half_day_hours: Some(half_day_hours),
+            } if billable.as_hours() <= half_day_hours => amount / 2.0,
+            Rate::Daily { amount, .. } => amount,
+        };
+        let amount = Money::from_major(charged * day.rate_multiplier());
+        (billable, amount)
+    }
+
+    // Each day is charged separately, so the totals match an invoice's line items
+    pub fn totals<'a>(&self, days: impl Iterator<Item = &'a DayEntry>) -> Totals {
+        let mut billable_hours = Hours::default();
+        let mut subtotal = Money::default();
+        for day in days {
+            let (billable, amount) = self.charge(day);
+            billable_hours += billable;
+            subtotal.0 += amount.0;
+        }
+
+        let tax_percent = self.tax_percent.unwrap_or(0.0);
+        let tax = Money((subtotal.0 as f64 * tax_percent / 100.0).round() as i64);
+
+        Totals {
+            currency: self.currency.clone(),
+            billable_hours,
+            subtotal,
+            tax_percent: self.tax_percent,
+            tax,
+            total: Money(subtotal.0 + tax.0),
+        }
+    }
+}
+
+impl Totals {
+    // The summary lines for a timesheet, as label and amount
+    pub fn lines(&self) -> Vec<(String, String)> {
+        let mut lines = vec![
+            (
+                "Billable hours".to_string(),
+                self.billable_hours.to_string(),
+            ),
+            (
+                "Subtotal".to_string(),
+                format!("{} {}", self.currency, self.subtotal),
+            ),
+        ];
+        if let Some(tax_percent) = self.tax_percent {
+            lines.push((
+                format!("Tax ({}%)", tax_percent),
+                format!("{} {}", self.currency, self.tax),
+            ));
+        }
+        lines.push((
+            "Total".to_string(),
+            format!("{} {}", self.currency, self.total),
+        ));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timesheet::DayOff;
+
+    fn days(hours: &[f64]) -> Vec<DayEntry> {
+        hours
+            .iter()
+            .map(|hours| DayEntry::new(Hours::from_hours(*hours)))
+            .collect()
+    }
+
+    #[test]
+    fn it_totals_an_hourly_rate_with_tax_and_rounding() {
+        let config: RateConfig = serde_json::from_str(
+            r#"{"unit":"hourly","amount":62.5,"currency":"GBP","tax_percent":20,
+                "rounding":{"increment_minutes":15,"mode":"up"}}"#,
+        )
+        .unwrap();
+
+        // 7h50m rounds up to 8h, 6h exactly stays as it is
+        let totals = config.totals(days(&[7.0 + 50.0 / 60.0, 6.0]).iter());
+        assert_eq!(totals.billable_hours, Hours::from_hours(14.0));
+        assert_eq!(totals.subtotal, Money(87500));
+        assert_eq!(totals.tax, Money(17500));
+        assert_eq!(totals.total.to_string(), "1050.00");
+        assert_eq!(
+            totals.lines()[2],
+            ("Tax (20%)".to_string(), "GBP 175.00".to_string())
+        );
+    }
+
+    #[test]
+    fn it_totals_a_day_rate() {
+        let config: RateConfig =
+            serde_json::from_str(r#"{"unit":"daily","amount":450,"currency":"EUR"}"#).unwrap();
+
+        // a short day and a long day are both a whole day
+        let mut days = days(&[7.5, 3.0, 10.0, 0.0]);
+        let totals = config.totals(days.iter());
+        assert_eq!(totals.subtotal, Money(135000));
+        assert_eq!(totals.billable_hours, Hours::from_hours(20.5));
+        assert_eq!(totals.tax, Money(0));
+        assert_eq!(totals.total, totals.subtotal);
+        assert_eq!(totals.lines().len(), 3);
+
+        // a bank holiday charged at double time
+        days[2].day_off = Some(DayOff {
+            reason: "Boxing Day".to_string(),
+            overtime: Some(2.0),
+            flagged: false,
+        });
+        assert_eq!(config.totals(days.iter()).subtotal, Money(180000));
+    }
+
+    #[test]
+    fn it_charges_half_a_day_up_to_the_threshold() {
+        let config: RateConfig = serde_json::from_str(
+            r#"{"unit":"daily","amount":450,"half_day_hours":4,"currency":"EUR"}"#,
+        )
+        .unwrap();
+
+        let days = days(&[4.0, 4.25, 10.0]);
+        let charges: Vec<Money> = days.iter().map(|day| config.charge(day).1).collect();
+        assert_eq!(charges, vec![Money(22500), Money(45000), Money(45000)]);
+
+        // the threshold applies to the hours after rounding
+        let nearest = Rounding {
+            increment_minutes: 30,
+            mode: RoundingMode::Nearest,
+        };
+        let config = RateConfig {
+            rounding: Some(nearest.clone()),
+            ..config
+        };
+        let short_day = DayEntry::new(Hours::from_minutes(4 * 60 + 10));
+        assert_eq!(config.charge(&short_day).1, Money(22500));
+        assert_eq!(
+            nearest.round(Hours::from_minutes(44)),
+            Hours::from_minutes(30)
+        );
+        assert_eq!(
+            nearest.round(Hours::from_minutes(45)),
+            Hours::from_minutes(60)
+        );
+    }
+}
@@ -1,19 +1,23 @@
+use crate::calendar::WorkingCalendar;
+use crate::commits::{CommitFilter, Identity, NotesConfig, TimeConfig};
+use crate::estimate::HoursConfig;
+use crate::rates::RateConfig;
+use crate::timesheet::{DayOverride, TimeEntry, Timesheet};
 use crate::utils;
+use chrono::NaiveDate;
 use exitcode;
 use regex;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use serde_json::{Map, Value};
-use std::error::Error;
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process;
 
 // Creates a new repository struct after being sent data from git2.
 // It returns the namespace and path, but also init date for the repo and probably other stuff
 // Basically sanitise the data from git2 into something usable
-#[derive(PartialEq, Debug, Serialize, Deserialize)]
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Repo {
     pub namespace: String,
     pub path: String,
@@ -22,7 +26,82 @@ pub struct Repo {
     pub client_name: String,
     pub contact_person: String,
     pub address: String,
-    pub timesheet: Map<String, Value>,
+    // What the client is charged, for totals on the timesheet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rates: Option<RateConfig>,
+    pub timesheet: Timesheet,
+    #[serde(default)]
+    pub hours: HoursConfig,
+    #[serde(default)]
+    pub notes: NotesConfig,
+    // The timezone and hour that commits are bucketed into days with
+    #[serde(default)]
+    pub time: TimeConfig,
+    // The working week, public holidays and what to do with commits outside of them
+    #[serde(default)]
+    pub calendar: WorkingCalendar,
+    // A regex for the ticket keys in commit messages and branch names e.g. "[A-Z]+-\\d+"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue_pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub identities: Vec<Identity>,
+    // Further repositories whose commits are merged into this timesheet
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repositories: Vec<String>,
+    // The refs read from each repository, whether merges count and, in a repository
+    // shared with other projects, the paths that belong to this one. Keyed by
+    // repository path
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub commit_filters: BTreeMap<String, CommitFilter>,
+    // Time logged with 'timesheet-gen log' and 'leave', merged with the git log
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<TimeEntry>,
+    // Days changed with 'timesheet-gen edit', applied over the git log
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<NaiveDate, DayOverride>,
+}
+
+// Client details passed as flags, so that init can run without prompting
+#[derive(PartialEq, Debug, Default)]
+pub struct ClientOptions {
+    pub client_name: Option<String>,
+    pub contact_person: Option<String>,
+    pub address: Option<String>,
+    pub assume_yes: bool,
+}
+
+impl ClientOptions {
+    fn is_given(&self) -> bool {
+        self.assume_yes
+            || self.client_name.is_some()
+            || self.contact_person.is_some()
+            || self.address.is_some()
+    }
+}
+
+// Addresses are entered comma separated and stored one part per line
+pub fn format_address(address: &str) -> String {
+    let regex = Regex::new(r",\s*").unwrap();
+    regex.replace_all(address, ",\n").to_string()
+}
+
+// Get repo name by finding the name of the root directory
+pub fn namespace_from_path(git_filepath: &Path) -> Result<String, regex::Error> {
+    let reg = regex::Regex::new(r"(?P<namespace>[^/][\w\d]+)/\.git/")?;
+    let namespace = git_filepath
+        .to_str()
+        .and_then(|path| reg.captures(path))
+        .and_then(|cap| cap.name("namespace"))
+        .map(|namespace| namespace.as_str().to_string())
+        .unwrap_or_default();
+
+    Ok(namespace)
+}
+
+// Where a repository's git directory really is, however its path is written
+fn git_dir(path: &str) -> Option<PathBuf> {
+    let repository = git2::Repository::open(path).ok()?;
+    fs::canonicalize(repository.path()).ok()
 }
 
 //TODO: get date out of the repository object
@@ -35,26 +114,13 @@ impl Repo {
         client_name: String,
         contact_person: String,
         address: String,
-        timesheet: Map<String, Value>,
+        timesheet: Timesheet,
     ) -> Result<Repo, regex::Error> {
-        let mut namespace = String::new();
-        // Get repo name by finding the name of the root directory
         let path = git_filepath.display().to_string();
 
-        // TODO this will fail at runtime if the git path is incorrect
-        match repo_name {
-            Some(arg) => namespace = arg,
-            None => {
-                let reg = regex::Regex::new(r"(?P<namespace>[^/][\w\d]+)/\.git/")?;
-                if let Some(cap) = reg
-                    .captures(&git_filepath.to_str().unwrap())
-                    .unwrap()
-                    .name("namespace")
-                {
-                    // parse into String
-                    namespace = (&cap.as_str()).parse().unwrap();
-                }
-            }
+        let namespace = match repo_name {
+            Some(arg) => arg,
+            None => namespace_from_path(git_filepath)?,
         };
 
         Ok(Repo {
@@ -65,16 +131,135 @@ impl Repo {
             client_name,
             contact_person,
             address,
+            rates: None,
             timesheet,
+            hours: HoursConfig::default(),
+            notes: NotesConfig::default(),
+            time: TimeConfig::default(),
+            calendar: WorkingCalendar::default(),
+            issue_pattern: None,
+            identities: vec![],
+            repositories: vec![],
+            commit_filters: BTreeMap::new(),
+            entries: vec![],
+            overrides: BTreeMap::new(),
         })
     }
 
-    pub fn prompt_for_client_details(&mut self) -> &Repo {
+    // The identities whose commits count toward the timesheet, falling back
+    // to the name and email that git is configured with. The fallback is as
+    // loose as the `git log --author` filter it replaced, ignoring case
+    pub fn author_identities(&self) -> Vec<Identity> {
+        if !self.identities.is_empty() {
+            return self.identities.clone();
+        }
+
+        let mut identities = vec![];
+        if !self.name.is_empty() {
+            identities.push(Identity::from_author(&self.name));
+        }
+        if !self.email.is_empty() {
+            identities.push(Identity::from_email(&self.email));
+        }
+        identities
+    }
+
+    // The git paths of every repository in the project, the original one first
+    pub fn repository_paths(&self) -> Vec<&str> {
+        let mut paths = vec![self.path.as_str()];
+        paths.extend(self.repositories.iter().map(|path| path.as_str()));
+        paths
+    }
+
+    // The commit filters keyed by the repository's path as the project stores
+    // it, so that a filter written as "/x/repo" or "/x/repo/.git" is found for
+    // "/x/repo/.git/". A filter for another repository is an error, as it would
+    // otherwise be ignored without a word, reading every commit in the repository.
+    // One whose repository can't be opened, e.g. on another machine, is skipped
+    pub fn resolve_commit_filters(&self) -> Result<BTreeMap<String, CommitFilter>, String> {
+        let mut resolved = BTreeMap::new();
+        if self.commit_filters.is_empty() {
+            return Ok(resolved);
+        }
+
+        let paths = self.repository_paths();
+        let git_dirs: Vec<Option<PathBuf>> = paths.iter().map(|path| git_dir(path)).collect();
+
+        for (key, filter) in self.commit_filters.iter() {
+            let index = match paths.iter().position(|path| path == key) {
+                Some(index) => index,
+                None => {
+                    let key_dir = match git_dir(key) {
+                        Some(key_dir) => key_dir,
+                        None => {
+                            eprintln!(
+                                "Skipping the commit filter for '{}' in {}, it isn't a repository that can be opened",
+                                key, self.namespace
+                            );
+                            continue;
+                        }
+                    };
+                    git_dirs
+                        .iter()
+                        .position(|dir| *dir == Some(key_dir.clone()))
+                        .ok_or_else(|| {
+                            format!(
+                                "The commit filter for '{}' in {} isn't for one of its repositories: {}",
+                                key,
+                                self.namespace,
+                                paths.join(", ")
+                            )
+                        })?
+                }
+            };
+            if resolved
+                .insert(paths[index].to_string(), filter.clone())
+                .is_some()
+            {
+                return Err(format!(
+                    "There's more than one commit filter for '{}' in {}",
+                    paths[index], self.namespace
+                ));
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    // Returns false if the repository is already part of the project
+    pub fn add_repository(&mut self, git_filepath: &Path) -> bool {
+        let path = git_filepath.display().to_string();
+        if self.repository_paths().contains(&path.as_str()) {
+            return false;
+        }
+
+        self.repositories.push(path);
+        true
+    }
+
+    pub fn prompt_for_client_details(&mut self, options: &ClientOptions) -> &Repo {
+        if options.is_given() {
+            self.use_client_details(options);
+            return self;
+        }
+
         println!("Would you like to add a client for this repository? Y/n");
         self.use_client_option();
         self
     }
 
+    fn use_client_details(&mut self, options: &ClientOptions) {
+        if let Some(client_name) = &options.client_name {
+            self.client_name = client_name.to_owned();
+        }
+        if let Some(contact_person) = &options.contact_person {
+            self.contact_person = contact_person.to_owned();
+        }
+        if let Some(address) = &options.address {
+            self.address = format_address(address);
+        }
+    }
+
     fn use_client_option(&mut self) {
         let input = utils::read_input().to_lowercase();
         let option = Option::from(&*input);
@@ -99,20 +284,7 @@ impl Repo {
 
         println!("Address (comma seperated):");
         let address = utils::read_input();
-        let regex = Regex::new(r",\s*").unwrap();
-        let result = regex.replace_all(&address, ",\n");
-        self.address = String::from(result);
-    }
-
-    pub fn write_config_file(&self, config_path: &String) -> Result<(), Box<dyn Error>> {
-        let json = serde_json::to_string(&self).unwrap();
-        let mut file = File::create(&config_path)?;
-
-        file.write_all(json.as_bytes())?;
-        println!(
-            "timesheet-gen initialised. Try 'timesheet-gen make' to create your first timesheet."
-        );
-        process::exit(exitcode::OK);
+        self.address = format_address(&address);
     }
 }
 
@@ -136,7 +308,18 @@ mod tests {
             client_name: "".to_string(),
             contact_person: "".to_string(),
             address: "".to_string(),
-            timesheet: Map::new(),
+            rates: None,
+            timesheet: Timesheet::new(),
+            hours: HoursConfig::default(),
+            notes: NotesConfig::default(),
+            time: TimeConfig::default(),
+            calendar: WorkingCalendar::default(),
+            issue_pattern: None,
+            identities: vec![],
+            repositories: vec![],
+            commit_filters: BTreeMap::new(),
+            entries: vec![],
+            overrides: BTreeMap::new(),
         };
 
         let repo = Repo::new(
@@ -147,8 +330,46 @@ mod tests {
             "".to_string(),
             "".to_string(),
             "".to_string(),
-            Map::new(),
+            Timesheet::new(),
         );
         assert_eq!(repo.unwrap().namespace, mock_repo.namespace);
     }
+
+    #[test]
+    fn it_keys_commit_filters_by_the_projects_repository_paths() {
+        let directory = std::env::temp_dir().join(utils::generate_random_path());
+        let repository = Repository::init(&directory).unwrap();
+        let git_path = repository.path().display().to_string();
+        let only_acme = CommitFilter {
+            paths: vec!["services/acme/**".to_string()],
+            ..Default::default()
+        };
+        let mut repo = Repo {
+            namespace: "acme".to_string(),
+            path: git_path.clone(),
+            ..Default::default()
+        };
+
+        // written as the working directory, without .git/
+        repo.commit_filters
+            .insert(directory.display().to_string(), only_acme.clone());
+        assert_eq!(
+            repo.resolve_commit_filters().unwrap().get(&git_path),
+            Some(&only_acme)
+        );
+
+        // a repository that isn't on this machine
+        repo.commit_filters
+            .insert("/path/to/other/.git/".to_string(), CommitFilter::default());
+        assert_eq!(repo.resolve_commit_filters().unwrap().len(), 1);
+
+        let other = std::env::temp_dir().join(utils::generate_random_path());
+        Repository::init(&other).unwrap();
+        repo.commit_filters
+            .insert(other.display().to_string(), CommitFilter::default());
+        assert!(repo.resolve_commit_filters().is_err());
+
+        std::fs::remove_dir_all(&other).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
 }
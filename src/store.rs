@@ -0,0 +1,274 @@
+use crate::db;
+use crate::rates::Totals;
+use crate::repo::Repo;
+use crate::timesheet::Hours;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+// How long a published timesheet is available for
+pub const EXPIRE_TIME_SECONDS: i64 = 1800;
+
+// The published form of a timesheet, as read by timesheet-gen.io
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct TimesheetDocument {
+    pub creation_date: DateTime<Utc>,
+    pub random_path: String,
+    pub name: String,
+    pub email: String,
+    pub namespace: String,
+    pub path: String,
+    pub client_name: String,
+    pub client_contact_person: String,
+    pub address: String,
+    pub timesheet: String,
+    // The hours spent on each ticket, when the project has an issue pattern
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tickets: BTreeMap<String, Hours>,
+    // The money owed, when the project has a rate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totals: Option<Totals>,
+}
+
+impl TimesheetDocument {
+    pub fn new(repo: &Repo, random_path: String) -> TimesheetDocument {
+        TimesheetDocument {
+            creation_date: Utc::now(),
+            random_path,
+            name: repo.name.clone(),
+            email: repo.email.clone(),
+            namespace: repo.namespace.clone(),
+            path: repo.path.clone(),
+            client_name: repo.client_name.clone(),
+            client_contact_person: repo.contact_person.clone(),
+            address: repo.address.clone(),
+            timesheet: json!(repo.timesheet).to_string(),
+            tickets: repo.timesheet.ticket_hours(),
+            totals: repo.rates.as_ref().map(|rates| {
+                rates.totals(
+                    repo.timesheet
+                        .months
+                        .iter()
+                        .flat_map(|sheet| sheet.days.values()),
+                )
+            }),
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now - self.creation_date > Duration::seconds(EXPIRE_TIME_SECONDS)
+    }
+}
+
+#[async_trait]
+pub trait TimesheetStore {
+    // Store the timesheet and return where it can be viewed
+    async fn publish(&self, document: &TimesheetDocument) -> Result<String, Box<dyn Error>>;
+    async fn fetch(&self, random_path: &str) -> Result<Option<TimesheetDocument>, Box<dyn Error>>;
+    // Remove every timesheet that has expired, returning how many were removed
+    async fn expire(&self) -> Result<u64, Box<dyn Error>>;
+    // Remove a timesheet before it expires, returning false if it wasn't found
+    async fn revoke(&self, random_path: &str) -> Result<bool, Box<dyn Error>>;
+}
+
+// Generate random paths until one is found that isn't already in use
+pub async fn generate_random_path(
+    store: &(dyn TimesheetStore + Send + Sync),
+) -> Result<String, Box<dyn Error>> {
+    loop {
+        let random_path = crate::utils::generate_random_path();
+        if store.fetch(&random_path).await?.is_none() {
+            return Ok(random_path);
+        }
+    }
+}
+
+// Which store timesheets are published to, as set in the config file e.g.
+// { "backend": "local", "directory": "/home/me/timesheets" }
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum StorageConfig {
+    Mongo {
+        // falls back to the MONGODB_URI environment variable
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        uri: Option<String>,
+    },
+    Local {
+        directory: PathBuf,
+    },
+}
+
+impl Default for StorageConfig {
+    fn default() -> StorageConfig {
+        StorageConfig::Mongo { uri: None }
+    }
+}
+
+impl StorageConfig {
+    pub async fn store(&self) -> Result<Box<dyn TimesheetStore + Send + Sync>, Box<dyn Error>> {
+        match self {
+            StorageConfig::Mongo { uri } => Ok(Box::new(db::Db::new(uri.clone()).await?)),
+            StorageConfig::Local { directory } => Ok(Box::new(LocalStore::new(directory.clone()))),
+        }
+    }
+}
+
+// Writes each timesheet as a json file in a directory, for working offline
+pub struct LocalStore {
+    pub directory: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(directory: PathBuf) -> LocalStore {
+        LocalStore { directory }
+    }
+
+    // The path comes from the command line for revoke, so anything that isn't
+    // the generated form is refused rather than joined onto the directory
+    fn document_path(&self, random_path: &str) -> Result<PathBuf, Box<dyn Error>> {
+        if random_path.is_empty() || !random_path.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(format!("'{}' isn't a timesheet id", random_path).into());
+        }
+
+        Ok(self.directory.join(format!("{}.json", random_path)))
+    }
+}
+
+#[async_trait]
+impl TimesheetStore for LocalStore {
+    async fn publish(&self, document: &TimesheetDocument) -> Result<String, Box<dyn Error>> {
+        fs::create_dir_all(&self.directory)?;
+
+        let path = self.document_path(&document.random_path)?;
+        fs::write(&path, serde_json::to_string_pretty(document)?)?;
+
+        Ok(path.display().to_string())
+    }
+
+    async fn fetch(&self, random_path: &str) -> Result<Option<TimesheetDocument>, Box<dyn Error>> {
+        let path = self.document_path(random_path)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let document = serde_json::from_str(&fs::read_to_string(path)?)?;
+        Ok(Some(document))
+    }
+
+    async fn expire(&self) -> Result<u64, Box<dyn Error>> {
+        if !self.directory.exists() {
+            return Ok(0);
+        }
+
+        let now = Utc::now();
+        let mut expired = 0;
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+
+            // ignore anything in the directory that isn't a timesheet
+            let document: TimesheetDocument =
+                match serde_json::from_str(&fs::read_to_string(&path)?) {
+                    Ok(document) => document,
+                    Err(_) => continue,
+                };
+
+            if document.is_expired(now) {
+                fs::remove_file(&path)?;
+                expired += 1;
+            }
+        }
+
+        Ok(expired)
+    }
+
+    async fn revoke(&self, random_path: &str) -> Result<bool, Box<dyn Error>> {
+        let path = self.document_path(random_path)?;
+        if !path.exists() {
+            return Ok(false);
+        }
+
+        fs::remove_file(path)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    fn document(random_path: &str, creation_date: DateTime<Utc>) -> TimesheetDocument {
+        TimesheetDocument {
+            creation_date,
+            random_path: random_path.to_string(),
+            name: "Tom Jones".to_string(),
+            email: "sex_bomb@gmail.com".to_string(),
+            namespace: "timesheet".to_string(),
+            path: "/path/to/timesheet/.git/".to_string(),
+            client_name: "".to_string(),
+            client_contact_person: "".to_string(),
+            address: "".to_string(),
+            timesheet: "{\"months\":[]}".to_string(),
+            tickets: BTreeMap::new(),
+            totals: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn it_publishes_fetches_and_revokes_locally() {
+        let directory = std::env::temp_dir().join(utils::generate_random_path());
+        let store = LocalStore::new(directory.clone());
+        let published = document("abcdefghij", Utc::now());
+
+        let location = store.publish(&published).await.unwrap();
+        assert!(location.ends_with("abcdefghij.json"));
+        assert_eq!(store.fetch("abcdefghij").await.unwrap(), Some(published));
+
+        assert!(store.revoke("abcdefghij").await.unwrap());
+        assert!(!store.revoke("abcdefghij").await.unwrap());
+        assert_eq!(store.fetch("abcdefghij").await.unwrap(), None);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn it_refuses_paths_outside_the_local_store() {
+        let directory = std::env::temp_dir().join(utils::generate_random_path());
+        let outside = directory.with_extension("json");
+        fs::write(&outside, "{}").unwrap();
+        let store = LocalStore::new(directory.clone());
+        let escaping = format!("../{}", outside.file_stem().unwrap().to_string_lossy());
+
+        for random_path in [escaping.as_str(), "", "a/b", "..", "/etc/passwd"].iter() {
+            assert!(store.revoke(random_path).await.is_err());
+            assert!(store.fetch(random_path).await.is_err());
+        }
+        assert!(outside.exists());
+
+        fs::remove_file(outside).unwrap();
+    }
+
+    #[tokio::test]
+    async fn it_expires_old_timesheets_locally() {
+        let directory = std::env::temp_dir().join(utils::generate_random_path());
+        let store = LocalStore::new(directory.clone());
+        let an_hour_ago = Utc::now() - Duration::hours(1);
+
+        store.publish(&document("old", an_hour_ago)).await.unwrap();
+        store.publish(&document("new", Utc::now())).await.unwrap();
+
+        assert_eq!(store.expire().await.unwrap(), 1);
+        assert!(store.fetch("old").await.unwrap().is_none());
+        assert!(store.fetch("new").await.unwrap().is_some());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
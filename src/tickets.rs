@@ -0,0 +1,123 @@
+use crate::commits::Commit;
+use crate::timesheet::Hours;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+
+// The ticket keys a commit refers to, from its message and the branches it was
+// made on e.g. ABC-123 from "ABC-123 Add login" or feature/ABC-123-login
+pub fn ticket_keys(pattern: &Regex, commit: &Commit) -> BTreeSet<String> {
+    std::iter::once(commit.message.as_str())
+        .chain(commit.branches.iter().map(|branch| branch.as_str()))
+        .flat_map(|text| pattern.find_iter(text))
+        .map(|key| key.as_str().to_string())
+        .collect()
+}
+
+// Split a day's hours across the tickets its commits refer to. Each commit counts
+// equally, shared between the tickets it refers to. The share of commits without a
+// ticket isn't given to any ticket, and minutes lost to rounding go to the
+// largest remainders so the shares add up
+pub fn apportion(hours: Hours, commit_keys: &[BTreeSet<String>]) -> BTreeMap<String, Hours> {
+    let mut weights: BTreeMap<Option<&str>, f64> = BTreeMap::new();
+    for keys in commit_keys {
+        if keys.is_empty() {
+            *weights.entry(None).or_default() += 1.0;
+        }
+        for key in keys {
+            *weights.entry(Some(key.as_str())).or_default() += 1.0 / keys.len() as f64;
+        }
+    }
+
+    let total_weight: f64 = weights.values().sum();
+    if weights.keys().all(|key| key.is_none()) {
+        return BTreeMap::new();
+    }
+
+    let minutes = hours.as_minutes() as f64;
+    let mut shares: Vec<(Option<&str>, u32, f64)> = weights
+        .iter()
+        .map(|(key, weight)| {
+            let share = minutes * weight / total_weight;
+            (*key, share.floor() as u32, share - share.floor())
+        })
+        .collect();
+
+    let allocated: u32 = shares.iter().map(|(_, minutes, _)| minutes).sum();
+    let mut by_remainder: Vec<usize> = (0..shares.len()).collect();
+    by_remainder.sort_by(|a, b| shares[*b].2.total_cmp(&shares[*a].2));
+    for index in by_remainder
+        .into_iter()
+        .take((hours.as_minutes() - allocated) as usize)
+    {
+        shares[index].1 += 1;
+    }
+
+    shares
+        .into_iter()
+        .filter_map(|(key, minutes, _)| {
+            key.map(|key| (key.to_string(), Hours::from_minutes(minutes)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn keys(keys: &[&str]) -> BTreeSet<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    #[test]
+    fn it_finds_keys_in_messages_and_branches() {
+        let commit = Commit {
+            id: "1".to_string(),
+            author_name: "Tom Jones".to_string(),
+            author_email: "sex_bomb@gmail.com".to_string(),
+            author_time: DateTime::parse_from_rfc3339("2021-10-04T09:00:00+01:00").unwrap(),
+            committer_time: DateTime::parse_from_rfc3339("2021-10-04T09:00:00+01:00").unwrap(),
+            summary: "ABC-12 Add login".to_string(),
+            message: "ABC-12 Add login\n\nAlso fixes ABC-7 and #40".to_string(),
+            is_merge: false,
+            branches: vec!["feature/XYZ-3-login".to_string()],
+        };
+
+        let jira = Regex::new(r"[A-Z]+-\d+").unwrap();
+        assert_eq!(
+            ticket_keys(&jira, &commit),
+            keys(&["ABC-12", "ABC-7", "XYZ-3"])
+        );
+
+        let github = Regex::new(r"#\d+").unwrap();
+        assert_eq!(ticket_keys(&github, &commit), keys(&["#40"]));
+    }
+
+    #[test]
+    fn it_apportions_hours_across_tickets() {
+        let tickets = apportion(
+            Hours::from_hours(8.0),
+            &[
+                keys(&["ABC-1"]),
+                keys(&["ABC-1", "ABC-2"]),
+                keys(&[]),
+                keys(&["ABC-3"]),
+            ],
+        );
+        assert_eq!(tickets["ABC-1"], Hours::from_hours(3.0));
+        assert_eq!(tickets["ABC-2"], Hours::from_hours(1.0));
+        assert_eq!(tickets["ABC-3"], Hours::from_hours(2.0));
+
+        // 7 hours across 3 tickets doesn't divide into whole minutes
+        let tickets = apportion(
+            Hours::from_hours(7.0),
+            &[keys(&["ABC-1"]), keys(&["ABC-2"]), keys(&["ABC-3"])],
+        );
+        assert_eq!(
+            tickets.values().copied().sum::<Hours>(),
+            Hours::from_hours(7.0)
+        );
+
+        assert!(apportion(Hours::from_hours(8.0), &[keys(&[])]).is_empty());
+    }
+}
@@ -0,0 +1,754 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::{Add, AddAssign};
+
+// Hours worked, stored as whole minutes so that totals don't drift.
+// Serialised as decimal hours e.g. 7.5
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Default)]
+pub struct Hours {
+    minutes: u32,
+}
+
+impl Hours {
+    pub fn from_minutes(minutes: u32) -> Hours {
+        Hours { minutes }
+    }
+
+    pub fn from_hours(hours: f64) -> Hours {
+        Hours {
+            minutes: (hours * 60.0).round().max(0.0) as u32,
+        }
+    }
+
+    pub fn as_hours(&self) -> f64 {
+        self.minutes as f64 / 60.0
+    }
+
+    pub fn as_minutes(&self) -> u32 {
+        self.minutes
+    }
+}
+
+impl Add for Hours {
+    type Output = Hours;
+
+    fn add(self, other: Hours) -> Hours {
+        Hours::from_minutes(self.minutes + other.minutes)
+    }
+}
+
+impl AddAssign for Hours {
+    fn add_assign(&mut self, other: Hours) {
+        self.minutes += other.minutes;
+    }
+}
+
+impl std::iter::Sum for Hours {
+    fn sum<I: Iterator<Item = Hours>>(iter: I) -> Hours {
+        iter.fold(Hours::default(), |total, hours| total + hours)
+    }
+}
+
+impl fmt::Display for Hours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // two decimal places at most, without trailing zeros e.g. 8, 7.5, 1.25
+        let hours = format!("{:.2}", self.as_hours());
+        let hours = hours.trim_end_matches('0').trim_end_matches('.');
+        write!(f, "{}", hours)
+    }
+}
+
+impl Serialize for Hours {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64((self.as_hours() * 100.0).round() / 100.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Hours {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Hours, D::Error> {
+        let hours = f64::deserialize(deserializer)?;
+        Ok(Hours::from_hours(hours))
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DayEntry {
+    pub hours: Hours,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    // The namespaces of the repositories that had commits on this day
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub repositories: BTreeSet<String>,
+    // The day's hours split across the tickets its commits refer to
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tickets: BTreeMap<String, Hours>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day_off: Option<DayOff>,
+    // Hours logged with 'timesheet-gen log', by kind. They are part of the day's hours
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub logged: BTreeMap<EntryKind, Hours>,
+    // Set when the day was taken as leave with 'timesheet-gen leave'
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leave: Option<EntryKind>,
+}
+
+impl DayEntry {
+    pub fn new(hours: Hours) -> DayEntry {
+        DayEntry {
+            hours,
+            ..Default::default()
+        }
+    }
+
+    // What the day's rate is multiplied by, more than 1 for overtime
+    pub fn rate_multiplier(&self) -> f64 {
+        self.day_off
+            .as_ref()
+            .and_then(|day_off| day_off.overtime)
+            .unwrap_or(1.0)
+    }
+
+    // The hours from the git log, which are what the tickets split. Time logged
+    // by hand is part of the day's hours but never on a ticket
+    pub fn development_hours(&self) -> Hours {
+        let logged: Hours = self.logged.values().copied().sum();
+        Hours::from_minutes(self.hours.as_minutes().saturating_sub(logged.as_minutes()))
+    }
+
+    // The day's hours by kind, with None for the hours from the git log
+    pub fn categories(&self) -> Vec<(Option<EntryKind>, Hours)> {
+        let development = self.development_hours();
+
+        let mut categories = vec![];
+        if development > Hours::default() {
+            categories.push((None, development));
+        }
+        for (kind, hours) in self.logged.iter() {
+            categories.push((Some(*kind), *hours));
+        }
+        categories
+    }
+
+    // The note, after any leave, time logged by hand and why the day isn't a
+    // working day if it isn't one e.g. "[Vacation] [Meeting 2] sprint planning"
+    pub fn remarks(&self) -> Option<String> {
+        let mut remarks: Vec<String> = vec![];
+        if let Some(day_off) = &self.day_off {
+            remarks.push(format!("[{}]", day_off));
+        }
+        if let Some(leave) = &self.leave {
+            remarks.push(format!("[{}]", leave.label()));
+        }
+        for (kind, hours) in self.logged.iter() {
+            remarks.push(format!("[{} {}]", kind.label(), hours));
+        }
+        remarks.extend(self.note.clone());
+
+        match remarks.is_empty() {
+            true => None,
+            false => Some(remarks.join(" ")),
+        }
+    }
+}
+
+// Hours as the same share of a new total as they were of the old one
+fn scale(hours: Hours, from: Hours, to: Hours) -> Hours {
+    match from.as_minutes() {
+        0 => Hours::default(),
+        from => Hours::from_minutes(
+            (hours.as_minutes() as u64 * to.as_minutes() as u64 / from as u64) as u32,
+        ),
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+// Why commits were made on a day that isn't a working day, and what the
+// project's policy for those days did with it
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct DayOff {
+    // the holiday's name, or the day of the week
+    pub reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overtime: Option<f64>,
+    // still to be reviewed, which is only ever shown to the contractor
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub flagged: bool,
+}
+
+// As the client sees it, so without the review flag
+impl fmt::Display for DayOff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.reason)?;
+        if let Some(multiplier) = self.overtime {
+            write!(f, ", overtime x{}", multiplier)?;
+        }
+        Ok(())
+    }
+}
+
+// A change made to a day with 'timesheet-gen edit', kept in the config file
+// and applied over the days found in the git log every time a timesheet is made
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DayOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hours: Option<Hours>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    // include the day even if there were no commits on it
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub added: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub removed: bool,
+}
+
+impl DayOverride {
+    // Combine a new edit with any earlier ones, so that e.g. setting a note
+    // keeps the hours that were set before. Removing a day discards the rest
+    pub fn merge(&mut self, edit: DayOverride) {
+        if edit.removed {
+            *self = edit;
+            return;
+        }
+
+        self.hours = edit.hours.or(self.hours);
+        self.note = edit.note.or_else(|| self.note.take());
+        self.added |= edit.added;
+        self.removed = false;
+    }
+}
+
+// What time logged by hand was spent on. The last four are kinds of leave
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    Meeting,
+    Workshop,
+    Travel,
+    Admin,
+    Other,
+    Vacation,
+    Sick,
+    Parental,
+    Unpaid,
+}
+
+impl EntryKind {
+    pub fn is_leave(&self) -> bool {
+        matches!(
+            self,
+            EntryKind::Vacation | EntryKind::Sick | EntryKind::Parental | EntryKind::Unpaid
+        )
+    }
+
+    // The name of a category of time, where None is the time from the git log
+    pub fn category_label(kind: Option<EntryKind>) -> &'static str {
+        kind.map_or("Development", |kind| kind.label())
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EntryKind::Meeting => "Meeting",
+            EntryKind::Workshop => "Workshop",
+            EntryKind::Travel => "Travel",
+            EntryKind::Admin => "Admin",
+            EntryKind::Other => "Other",
+            EntryKind::Vacation => "Vacation",
+            EntryKind::Sick => "Sick leave",
+            EntryKind::Parental => "Parental leave",
+            EntryKind::Unpaid => "Unpaid leave",
+        }
+    }
+}
+
+// Time that doesn't produce commits, logged with 'timesheet-gen log' or 'leave'
+// and kept in the config file. Leave has no hours
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub date: NaiveDate,
+    pub kind: EntryKind,
+    #[serde(default)]
+    pub hours: Hours,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct MonthSheet {
+    pub year: i32,
+    pub month: u32,
+    pub days: BTreeMap<NaiveDate, DayEntry>,
+}
+
+impl MonthSheet {
+    pub fn new(year: i32, month: u32) -> MonthSheet {
+        MonthSheet {
+            year,
+            month,
+            days: BTreeMap::new(),
+        }
+    }
+
+    pub fn total_hours(&self) -> Hours {
+        self.days.values().map(|day| day.hours).sum()
+    }
+
+    // Days taken as leave with nothing worked on them aren't counted
+    pub fn days_worked(&self) -> usize {
+        self.days
+            .values()
+            .filter(|day| day.hours > Hours::default())
+            .count()
+    }
+}
+
+// A timesheet is a list of months, kept in date order, each holding the days worked
+#[derive(PartialEq, Debug, Clone, Default, Serialize)]
+pub struct Timesheet {
+    pub months: Vec<MonthSheet>,
+}
+
+impl Timesheet {
+    pub fn new() -> Timesheet {
+        Timesheet { months: vec![] }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.months.iter().all(|month| month.days.is_empty())
+    }
+
+    fn month_mut(&mut self, year: i32, month: u32) -> &mut MonthSheet {
+        let index = match self
+            .months
+            .binary_search_by_key(&(year, month), |sheet| (sheet.year, sheet.month))
+        {
+            Ok(index) => index,
+            Err(index) => {
+                self.months.insert(index, MonthSheet::new(year, month));
+                index
+            }
+        };
+
+        &mut self.months[index]
+    }
+
+    pub fn insert_day(&mut self, date: NaiveDate, entry: DayEntry) {
+        self.month_mut(date.year(), date.month())
+            .days
+            .insert(date, entry);
+    }
+
+    fn day_mut(&mut self, date: NaiveDate) -> Option<&mut DayEntry> {
+        self.months
+            .iter_mut()
+            .find(|sheet| sheet.year == date.year() && sheet.month == date.month())
+            .and_then(|sheet| sheet.days.get_mut(&date))
+    }
+
+    // An edit to a day that isn't in the timesheet is only applied if it adds
+    // the day, so one that doesn't would be saved without changing anything
+    pub fn check_override(&self, date: NaiveDate, edit: &DayOverride) -> Result<(), String> {
+        let exists = self
+            .months
+            .iter()
+            .any(|sheet| sheet.days.contains_key(&date));
+        if exists || edit.added || edit.removed {
+            return Ok(());
+        }
+
+        Err(format!(
+            "{} has no commits or time logged, use --add to include it",
+            date.format("%A %-d %B %Y")
+        ))
+    }
+
+    pub fn apply_override(&mut self, date: NaiveDate, edit: &DayOverride) {
+        if edit.removed {
+            self.month_mut(date.year(), date.month()).days.remove(&date);
+            return;
+        }
+
+        if edit.added && self.day_mut(date).is_none() {
+            self.insert_day(date, DayEntry::default());
+        }
+
+        if let Some(day) = self.day_mut(date) {
+            // editing a day is reviewing it
+            if let Some(day_off) = day.day_off.as_mut() {
+                day_off.flagged = false;
+            }
+            if let Some(hours) = edit.hours {
+                // the edit sets the day's total. Time logged by hand is kept, cut
+                // down in proportion if it no longer fits, and the rest is split
+                // across the tickets in the same shares as before
+                let old_development = day.development_hours();
+                let logged: Hours = day.logged.values().copied().sum();
+                if logged > hours {
+                    for logged_hours in day.logged.values_mut() {
+                        *logged_hours = scale(*logged_hours, logged, hours);
+                    }
+                }
+                day.hours = hours;
+                let development = day.development_hours();
+                for ticket_hours in day.tickets.values_mut() {
+                    *ticket_hours = scale(*ticket_hours, old_development, development);
+                }
+            }
+            if let Some(note) = &edit.note {
+                day.note = Some(note.clone());
+            }
+        }
+    }
+
+    // Add time logged by hand to the day, alongside anything from the git log.
+    // The day's tickets are left as they are, since they split the git log's hours
+    pub fn add_entry(&mut self, entry: &TimeEntry) {
+        if self.day_mut(entry.date).is_none() {
+            self.insert_day(entry.date, DayEntry::default());
+        }
+
+        let day = self.day_mut(entry.date).unwrap();
+        match entry.kind.is_leave() {
+            true => day.leave = Some(entry.kind),
+            false => {
+                day.hours += entry.hours;
+                *day.logged.entry(entry.kind).or_default() += entry.hours;
+            }
+        }
+        if let Some(note) = &entry.note {
+            day.note = Some(match day.note.take() {
+                Some(existing) => format!("{}; {}", existing, note),
+                None => note.clone(),
+            });
+        }
+    }
+
+    // The hours spent on each ticket, across every day
+    pub fn ticket_hours(&self) -> BTreeMap<String, Hours> {
+        let mut tickets: BTreeMap<String, Hours> = BTreeMap::new();
+        for day in self.months.iter().flat_map(|sheet| sheet.days.values()) {
+            for (ticket, hours) in day.tickets.iter() {
+                *tickets.entry(ticket.clone()).or_default() += *hours;
+            }
+        }
+        tickets
+    }
+
+    pub fn total_hours(&self) -> Hours {
+        self.months.iter().map(|sheet| sheet.total_hours()).sum()
+    }
+}
+
+// Timesheets written before the typed model were nested maps of
+// year -> month abbreviation -> day -> { "hours": 8 }. The shape is told apart by
+// its keys, so that a mistake in a typed timesheet is reported as it is rather
+// than as a timesheet matching neither shape
+impl<'de> Deserialize<'de> for Timesheet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Timesheet, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let is_typed = match value.as_object() {
+            Some(object) => object.contains_key("months"),
+            None => true,
+        };
+
+        match is_typed {
+            true => serde_json::from_value(value)
+                .map(Timesheet::from_typed)
+                .map_err(de::Error::custom),
+            false => serde_json::from_value(value)
+                .map_err(de::Error::custom)
+                .and_then(|years| Timesheet::from_legacy(years).map_err(de::Error::custom)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TypedTimesheet {
+    months: Vec<MonthSheet>,
+}
+
+type LegacyTimesheet = BTreeMap<String, BTreeMap<String, BTreeMap<String, LegacyDay>>>;
+
+#[derive(Deserialize)]
+struct LegacyDay {
+    hours: f64,
+}
+
+impl Timesheet {
+    fn from_typed(typed: TypedTimesheet) -> Timesheet {
+        let mut timesheet = Timesheet::new();
+        for sheet in typed.months {
+            for (date, entry) in sheet.days {
+                timesheet.insert_day(date, entry);
+            }
+        }
+        timesheet
+    }
+
+    fn from_legacy(years: LegacyTimesheet) -> Result<Timesheet, String> {
+        let mut timesheet = Timesheet::new();
+        for (year, months) in years {
+            for (month, days) in months {
+                for (day, entry) in days {
+                    let date_string = format!("{} {} {}", year, month, day);
+                    let date = NaiveDate::parse_from_str(&date_string, "%Y %b %d")
+                        .map_err(|_| format!("'{}' is not a valid date", date_string))?;
+                    timesheet.insert_day(date, DayEntry::new(Hours::from_hours(entry.hours)));
+                }
+            }
+        }
+        Ok(timesheet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn it_keeps_months_in_date_order() {
+        let mut timesheet = Timesheet::new();
+        timesheet.insert_day(date(2021, 11, 2), DayEntry::new(Hours::from_hours(8.0)));
+        timesheet.insert_day(date(2020, 12, 31), DayEntry::new(Hours::from_hours(4.0)));
+        timesheet.insert_day(date(2021, 11, 1), DayEntry::new(Hours::from_minutes(90)));
+
+        let months: Vec<(i32, u32)> = timesheet
+            .months
+            .iter()
+            .map(|sheet| (sheet.year, sheet.month))
+            .collect();
+
+        assert_eq!(months, vec![(2020, 12), (2021, 11)]);
+        assert_eq!(timesheet.total_hours(), Hours::from_minutes(13 * 60 + 30));
+        assert_eq!(timesheet.total_hours().to_string(), "13.5");
+    }
+
+    #[test]
+    fn it_round_trips_through_json() {
+        let mut timesheet = Timesheet::new();
+        timesheet.insert_day(
+            date(2021, 10, 9),
+            DayEntry {
+                hours: Hours::from_hours(7.5),
+                note: Some("Client workshop".to_string()),
+                repositories: vec!["timesheet".to_string()].into_iter().collect(),
+                tickets: vec![("ABC-1".to_string(), Hours::from_hours(7.5))]
+                    .into_iter()
+                    .collect(),
+                day_off: Some(DayOff {
+                    reason: "Saturday".to_string(),
+                    overtime: None,
+                    flagged: true,
+                }),
+                logged: BTreeMap::new(),
+                leave: None,
+            },
+        );
+
+        let json = serde_json::to_string(&timesheet).unwrap();
+        assert_eq!(
+            json,
+            r#"{"months":[{"year":2021,"month":10,"days":{"2021-10-09":{"hours":7.5,"note":"Client workshop","repositories":["timesheet"],"tickets":{"ABC-1":7.5},"day_off":{"reason":"Saturday","flagged":true}}}}]}"#
+        );
+        assert_eq!(serde_json::from_str::<Timesheet>(&json).unwrap(), timesheet);
+    }
+
+    #[test]
+    fn it_reads_the_legacy_nested_map_format() {
+        let json = r#"{"2021":{"Oct":{"5":{"hours":8},"12":{"hours":8}},"Nov":{"1":{"hours":8}}}}"#;
+        let timesheet: Timesheet = serde_json::from_str(json).unwrap();
+
+        assert_eq!(timesheet.months.len(), 2);
+        assert_eq!(
+            timesheet.months[0].days.get(&date(2021, 10, 12)),
+            Some(&DayEntry::new(Hours::from_hours(8.0)))
+        );
+        assert_eq!(timesheet.total_hours(), Hours::from_hours(24.0));
+
+        let empty: Timesheet = serde_json::from_str("{}").unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn it_reports_what_is_wrong_with_a_typed_timesheet() {
+        let json = r#"{"months":[{"year":2021,"month":10,"days":{"2021-10-05":{"hours":"8"}}}]}"#;
+        let error = serde_json::from_str::<Timesheet>(json)
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("invalid type: string \"8\""), "{}", error);
+    }
+
+    #[test]
+    fn it_applies_edits_over_the_git_log() {
+        let mut timesheet = Timesheet::new();
+        timesheet.insert_day(date(2021, 9, 6), DayEntry::new(Hours::from_hours(8.0)));
+        timesheet.insert_day(date(2021, 9, 7), DayEntry::new(Hours::from_hours(8.0)));
+
+        let mut edit = DayOverride {
+            hours: Some(Hours::from_hours(5.5)),
+            ..Default::default()
+        };
+        edit.merge(DayOverride {
+            note: Some("Client workshop".to_string()),
+            ..Default::default()
+        });
+        timesheet.apply_override(date(2021, 9, 6), &edit);
+        timesheet.apply_override(
+            date(2021, 9, 7),
+            &DayOverride {
+                removed: true,
+                ..Default::default()
+            },
+        );
+        // without --add, an edit to a day with no commits is ignored
+        timesheet.apply_override(date(2021, 9, 8), &edit);
+        timesheet.apply_override(
+            date(2021, 9, 11),
+            &DayOverride {
+                hours: Some(Hours::from_hours(2.0)),
+                added: true,
+                ..Default::default()
+            },
+        );
+
+        let days = &timesheet.months[0].days;
+        assert_eq!(
+            days.keys().map(|day| day.day()).collect::<Vec<_>>(),
+            [6, 11]
+        );
+        assert_eq!(days[&date(2021, 9, 6)].hours, Hours::from_hours(5.5));
+        assert_eq!(
+            days[&date(2021, 9, 6)].note.as_deref(),
+            Some("Client workshop")
+        );
+        assert_eq!(timesheet.total_hours(), Hours::from_hours(7.5));
+    }
+
+    #[test]
+    fn it_refuses_edits_to_missing_days_unless_they_add_them() {
+        let mut timesheet = Timesheet::new();
+        timesheet.insert_day(date(2021, 9, 6), DayEntry::new(Hours::from_hours(8.0)));
+        let edit = DayOverride {
+            hours: Some(Hours::from_hours(2.0)),
+            ..Default::default()
+        };
+
+        assert!(timesheet.check_override(date(2021, 9, 6), &edit).is_ok());
+        assert_eq!(
+            timesheet.check_override(date(2021, 9, 8), &edit),
+            Err(
+                "Wednesday 8 September 2021 has no commits or time logged, use --add to include it"
+                    .to_string()
+            )
+        );
+        let added = DayOverride {
+            added: true,
+            ..edit
+        };
+        assert!(timesheet.check_override(date(2021, 9, 8), &added).is_ok());
+    }
+
+    #[test]
+    fn it_merges_time_logged_by_hand() {
+        let mut timesheet = Timesheet::new();
+        let mut day = DayEntry::new(Hours::from_hours(6.0));
+        day.note = Some("Add login".to_string());
+        timesheet.insert_day(date(2021, 9, 6), day);
+
+        timesheet.add_entry(&TimeEntry {
+            date: date(2021, 9, 6),
+            kind: EntryKind::Meeting,
+            hours: Hours::from_hours(2.0),
+            note: Some("sprint planning".to_string()),
+        });
+        timesheet.add_entry(&TimeEntry {
+            date: date(2021, 9, 7),
+            kind: EntryKind::Vacation,
+            hours: Hours::default(),
+            note: None,
+        });
+
+        let days = &timesheet.months[0].days;
+        let monday = &days[&date(2021, 9, 6)];
+        assert_eq!(monday.hours, Hours::from_hours(8.0));
+        assert_eq!(
+            monday.categories(),
+            vec![
+                (None, Hours::from_hours(6.0)),
+                (Some(EntryKind::Meeting), Hours::from_hours(2.0))
+            ]
+        );
+        assert_eq!(
+            monday.remarks().as_deref(),
+            Some("[Meeting 2] Add login; sprint planning")
+        );
+        assert_eq!(days[&date(2021, 9, 7)].leave, Some(EntryKind::Vacation));
+        assert_eq!(timesheet.total_hours(), Hours::from_hours(8.0));
+        assert_eq!(timesheet.months[0].days.len(), 2);
+        assert_eq!(timesheet.months[0].days_worked(), 1);
+    }
+
+    #[test]
+    fn it_keeps_logged_time_off_the_tickets_when_a_day_is_edited() {
+        let mut timesheet = Timesheet::new();
+        let mut day = DayEntry::new(Hours::from_hours(6.0));
+        day.tickets = vec![
+            ("ABC-1".to_string(), Hours::from_hours(4.0)),
+            ("ABC-2".to_string(), Hours::from_hours(2.0)),
+        ]
+        .into_iter()
+        .collect();
+        timesheet.insert_day(date(2021, 9, 6), day);
+        timesheet.add_entry(&TimeEntry {
+            date: date(2021, 9, 6),
+            kind: EntryKind::Meeting,
+            hours: Hours::from_hours(2.0),
+            note: None,
+        });
+        assert_eq!(timesheet.ticket_hours()["ABC-1"], Hours::from_hours(4.0));
+
+        // the meeting is kept and the development time shrinks to fit
+        let edit = DayOverride {
+            hours: Some(Hours::from_hours(5.0)),
+            ..Default::default()
+        };
+        timesheet.apply_override(date(2021, 9, 6), &edit);
+        let monday = &timesheet.months[0].days[&date(2021, 9, 6)];
+        assert_eq!(monday.hours, Hours::from_hours(5.0));
+        assert_eq!(
+            monday.categories(),
+            vec![
+                (None, Hours::from_hours(3.0)),
+                (Some(EntryKind::Meeting), Hours::from_hours(2.0))
+            ]
+        );
+        assert_eq!(monday.tickets["ABC-1"], Hours::from_hours(2.0));
+        assert_eq!(monday.tickets["ABC-2"], Hours::from_hours(1.0));
+
+        // cut below the meeting, which then takes the whole day
+        let edit = DayOverride {
+            hours: Some(Hours::from_hours(1.0)),
+            ..Default::default()
+        };
+        timesheet.apply_override(date(2021, 9, 6), &edit);
+        let monday = &timesheet.months[0].days[&date(2021, 9, 6)];
+        assert_eq!(
+            monday.categories(),
+            vec![(Some(EntryKind::Meeting), Hours::from_hours(1.0))]
+        );
+        assert!(monday
+            .tickets
+            .values()
+            .all(|hours| *hours == Hours::default()));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::config::{Commands, GetCommand, Initialise, Make};
+use crate::config::{Commands, Edit, GetCommand, Initialise, Invoice, Log, Make, Revoke, Show};
 use crate::repo;
 
 #[cfg(test)]
@@ -6,25 +6,12 @@ use crate::mock_repo_dep::MockRepository as Repository;
 #[cfg(not(test))]
 use git2::Repository;
 
-use serde_json::Map;
+use crate::timesheet::Timesheet;
 
 use std::error::Error;
 use std::{io, process};
 
 use random_string::generate;
-use std::collections::HashSet;
-
-impl std::str::FromStr for Commands {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "init" | "-i" => Ok(Commands::Init),
-            "make" | "-m" => Ok(Commands::Make),
-            _ => Err(format!("'{}' is not a valid value for Commands", s)),
-        }
-    }
-}
 
 pub fn generate_random_path() -> String {
     let charset = "0123456789abcdefghijklmnopqrstuvwxyz";
@@ -58,7 +45,7 @@ pub fn find_repository_details(path: &str) -> Result<repo::Repo, Box<dyn Error>>
         "".to_string(),
         "".to_string(),
         "".to_string(),
-        Map::new(),
+        Timesheet::new(),
     )?)
 }
 
@@ -68,7 +55,7 @@ pub fn read_input() -> String {
     String::from(input.trim())
 }
 
-pub fn run<T: Make + Initialise + GetCommand>(config: T) {
+pub fn run<T: Make + Initialise + Show + Edit + Log + Invoice + Revoke + GetCommand>(config: T) {
     // Match the command against an enum of cli commands
     let command: Commands = config.get_command();
     match command {
@@ -80,27 +67,27 @@ pub fn run<T: Make + Initialise + GetCommand>(config: T) {
             eprintln!("Error generating timesheet: {}", err);
             process::exit(1);
         }),
+        Commands::Show => config.show().unwrap_or_else(|err| {
+            eprintln!("Error showing timesheet: {}", err);
+            process::exit(1);
+        }),
+        Commands::Edit => config.edit().unwrap_or_else(|err| {
+            eprintln!("Error editing timesheet: {}", err);
+            process::exit(1);
+        }),
+        Commands::Log => config.log().unwrap_or_else(|err| {
+            eprintln!("Error logging time: {}", err);
+            process::exit(1);
+        }),
+        Commands::Invoice => config.invoice().unwrap_or_else(|err| {
+            eprintln!("Error invoicing timesheet: {}", err);
+            process::exit(1);
+        }),
+        Commands::Revoke => config.revoke().unwrap_or_else(|err| {
+            eprintln!("Error removing timesheet: {}", err);
+            process::exit(1);
+        }),
     }
-
-    // If command isn't found, show help or suggest command somehow
-    println!("Command not found. Run 'timesheet-gen help' for list of commands")
-}
-
-// TODO matching is expensive, need to memoize these calls
-pub fn find_named_matches(dynamic_regex_string: String, string_to_match: &String) -> HashSet<&str> {
-    let regex = regex::Regex::new(&*dynamic_regex_string.as_str()).unwrap();
-
-    regex
-        .captures_iter(&string_to_match)
-        .map(|caps| {
-            regex
-                .capture_names()
-                .map(|o| o.and_then(|n| Some(caps.name(n)?.as_str())))
-                .flatten()
-                .collect::<Vec<&str>>()
-        })
-        .flatten()
-        .collect()
 }
 
 #[cfg(test)]
@@ -140,6 +127,36 @@ mod tests {
             }
         }
 
+        impl Show for MockConfig {
+            fn show(&self) -> Result<(), Box<dyn Error>> {
+                panic!("Wrong function called for command");
+            }
+        }
+
+        impl Edit for MockConfig {
+            fn edit(&self) -> Result<(), Box<dyn Error>> {
+                panic!("Wrong function called for command");
+            }
+        }
+
+        impl Log for MockConfig {
+            fn log(&self) -> Result<(), Box<dyn Error>> {
+                panic!("Wrong function called for command");
+            }
+        }
+
+        impl Invoice for MockConfig {
+            fn invoice(&self) -> Result<(), Box<dyn Error>> {
+                panic!("Wrong function called for command");
+            }
+        }
+
+        impl Revoke for MockConfig {
+            fn revoke(&self) -> Result<(), Box<dyn Error>> {
+                panic!("Wrong function called for command");
+            }
+        }
+
         impl GetCommand for MockConfig {
             fn get_command(&self) -> Commands {
                 Commands::Make
@@ -174,6 +191,36 @@ mod tests {
             }
         }
 
+        impl Show for MockConfig {
+            fn show(&self) -> Result<(), Box<dyn Error>> {
+                panic!("Wrong function called for command");
+            }
+        }
+
+        impl Edit for MockConfig {
+            fn edit(&self) -> Result<(), Box<dyn Error>> {
+                panic!("Wrong function called for command");
+            }
+        }
+
+        impl Log for MockConfig {
+            fn log(&self) -> Result<(), Box<dyn Error>> {
+                panic!("Wrong function called for command");
+            }
+        }
+
+        impl Invoice for MockConfig {
+            fn invoice(&self) -> Result<(), Box<dyn Error>> {
+                panic!("Wrong function called for command");
+            }
+        }
+
+        impl Revoke for MockConfig {
+            fn revoke(&self) -> Result<(), Box<dyn Error>> {
+                panic!("Wrong function called for command");
+            }
+        }
+
         impl GetCommand for MockConfig {
             fn get_command(&self) -> Commands {
                 Commands::Init
@@ -197,7 +244,7 @@ mod tests {
             "".to_string(),
             "".to_string(),
             "".to_string(),
-            Map::new(),
+            Timesheet::new(),
         );
         assert_eq!(
             find_repository_details("/path/to/.git/").unwrap(),